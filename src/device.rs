@@ -0,0 +1,353 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::client::{HttpReply, status_error};
+use crate::{OAuthClient, OAuthError, OAuthProvider, TokenRequestFormat, TokenResponse};
+
+const DEVICE_CODE_GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:device_code";
+const DEFAULT_INTERVAL_SECS: u64 = 5;
+const SLOW_DOWN_INCREMENT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceAuthorizationResponse {
+    pub device_code: String,
+    pub user_code: String,
+    pub verification_uri: String,
+    pub verification_uri_complete: Option<String>,
+    pub expires_in: u64,
+    #[serde(default = "default_interval")]
+    pub interval: u64,
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+    #[serde(skip, default = "Instant::now")]
+    pub issued_at: Instant,
+}
+
+impl DeviceAuthorizationResponse {
+    pub fn expires_at(&self) -> Instant {
+        self.issued_at + Duration::from_secs(self.expires_in)
+    }
+}
+
+fn default_interval() -> u64 {
+    DEFAULT_INTERVAL_SECS
+}
+
+#[cfg(feature = "qr")]
+impl DeviceAuthorizationResponse {
+    pub fn verification_qr(&self) -> String {
+        let url = self
+            .verification_uri_complete
+            .as_deref()
+            .unwrap_or(&self.verification_uri);
+        crate::qr::render(url).unwrap_or_else(|| url.to_string())
+    }
+}
+
+impl<P: OAuthProvider> OAuthClient<P> {
+    pub async fn start_device_flow(&self) -> Result<DeviceAuthorizationResponse, OAuthError> {
+        let url = self.provider().device_authorization_url().ok_or_else(|| {
+            OAuthError::UnsupportedOperation(format!(
+                "{} does not support the device authorization grant",
+                self.provider().id()
+            ))
+        })?;
+
+        let mut payload = vec![
+            ("client_id".to_string(), self.config().client_id.clone()),
+            ("scope".to_string(), self.effective_scope()),
+        ];
+        if let Some(secret) = &self.config().client_secret {
+            payload.push(("client_secret".to_string(), secret.clone()));
+        }
+
+        // RFC 8628 §3.1 requires form encoding whatever the token endpoint takes.
+        let HttpReply { status, body, .. } = self
+            .post_payload_as(url, &payload, TokenRequestFormat::Form)
+            .await?;
+        if !status.is_success() {
+            return Err(status_error(status, body));
+        }
+
+        serde_json::from_str(&body).map_err(|err| OAuthError::InvalidResponse {
+            message: err.to_string(),
+            body,
+        })
+    }
+
+    pub async fn poll_device_token(
+        &self,
+        device_code: &str,
+        interval: Duration,
+        expires_at: Instant,
+    ) -> Result<TokenResponse, OAuthError> {
+        // Waits go through the configured clock, so the deadline is kept on it too.
+        let clock = self.config().clock.clone();
+        let deadline = clock.now() + expires_at.saturating_duration_since(Instant::now());
+        let mut interval = interval;
+        loop {
+            if clock.now() + interval >= deadline {
+                return Err(OAuthError::DeviceCodeExpired);
+            }
+            clock.sleep(interval).await;
+
+            let mut payload = HashMap::new();
+            payload.insert("grant_type".to_string(), DEVICE_CODE_GRANT_TYPE.to_string());
+            payload.insert("device_code".to_string(), device_code.to_string());
+            payload.insert("client_id".to_string(), self.config().client_id.clone());
+            if let Some(secret) = &self.config().client_secret {
+                payload.insert("client_secret".to_string(), secret.clone());
+            }
+
+            match self.send_token_request(payload).await {
+                Ok(token) => return Ok(token),
+                Err(OAuthError::OAuthErrorResponse { error, .. })
+                    if error == "authorization_pending" => {}
+                Err(OAuthError::OAuthErrorResponse { error, .. }) if error == "slow_down" => {
+                    interval += SLOW_DOWN_INCREMENT;
+                }
+                Err(OAuthError::OAuthErrorResponse { error, .. }) if error == "expired_token" => {
+                    return Err(OAuthError::DeviceCodeExpired);
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    pub async fn run_device_flow<F>(&self, on_user_code: F) -> Result<TokenResponse, OAuthError>
+    where
+        F: FnOnce(&DeviceAuthorizationResponse),
+    {
+        let device = self.start_device_flow().await?;
+        on_user_code(&device);
+        self.poll_device_token(
+            &device.device_code,
+            Duration::from_secs(device.interval),
+            device.expires_at(),
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant, SystemTime};
+
+    use crate::test_support::{MockResponse, MockServer, TestProvider};
+    use crate::{Clock, OAuthClient, OAuthClientConfig, OAuthError, Sleep};
+
+    // Returns from every sleep at once and remembers how long it was asked to wait.
+    #[derive(Default)]
+    struct RecordingClock {
+        sleeps: Mutex<Vec<Duration>>,
+    }
+
+    impl Clock for RecordingClock {
+        fn now(&self) -> SystemTime {
+            SystemTime::UNIX_EPOCH
+        }
+
+        fn sleep(&self, duration: Duration) -> Sleep<'_> {
+            self.sleeps.lock().unwrap().push(duration);
+            Box::pin(async {})
+        }
+    }
+
+    #[cfg(feature = "qr")]
+    #[test]
+    fn verification_qr_renders_block_characters() {
+        let device: super::DeviceAuthorizationResponse = serde_json::from_str(
+            r#"{"device_code":"device","user_code":"ABCD-EFGH","verification_uri":"https://example.com/device","verification_uri_complete":"https://example.com/device?user_code=ABCD-EFGH","expires_in":600}"#,
+        )
+        .unwrap();
+        let qr = device.verification_qr();
+        assert!(!qr.is_empty());
+        assert!(qr.contains('█'));
+        assert!(!qr.contains("https://"));
+        // Four light modules on every side, as ISO/IEC 18004 requires.
+        assert!(qr.lines().take(2).all(|line| line.trim().is_empty()));
+        assert!(qr.lines().all(|line| line.starts_with("    ")));
+    }
+
+    #[tokio::test]
+    async fn run_device_flow_polls_until_token() {
+        let server = MockServer::start(vec![
+            MockResponse::json(
+                200,
+                r#"{"device_code":"device","user_code":"ABCD-EFGH","verification_uri":"https://example.com/device","expires_in":600,"interval":0}"#,
+            ),
+            MockResponse::json(400, r#"{"error":"authorization_pending"}"#),
+            MockResponse::json(200, r#"{"access_token":"access"}"#),
+        ]);
+        let provider = TestProvider {
+            device_authorization_url: Some(server.leak_url("/device")),
+            ..TestProvider::new(server.leak_url("/token"))
+        };
+        let config = OAuthClientConfig::new("client-id", "http://localhost:8765/callback")
+            .with_insecure_endpoints(true);
+        let client = OAuthClient::new(provider, config).unwrap();
+
+        let shown = Arc::new(Mutex::new(None));
+        let recorded = shown.clone();
+        let token = client
+            .run_device_flow(move |device| {
+                *recorded.lock().unwrap() = Some(device.user_code.clone());
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(token.access_token, "access");
+        assert_eq!(shown.lock().unwrap().as_deref(), Some("ABCD-EFGH"));
+
+        let requests = server.requests();
+        assert_eq!(requests[0].path, "/device");
+        assert_eq!(
+            requests[0].header("content-type"),
+            Some("application/x-www-form-urlencoded")
+        );
+        assert_eq!(
+            requests[0].params().get("client_id").map(String::as_str),
+            Some("client-id")
+        );
+        assert_eq!(
+            requests[2].params().get("grant_type").map(String::as_str),
+            Some("urn:ietf:params:oauth:grant-type:device_code")
+        );
+    }
+
+    #[tokio::test]
+    async fn start_device_flow_surfaces_oauth_errors() {
+        let server = MockServer::start(vec![MockResponse::json(
+            400,
+            r#"{"error":"invalid_client","error_description":"unknown client"}"#,
+        )]);
+        let provider = TestProvider {
+            device_authorization_url: Some(server.leak_url("/device")),
+            ..TestProvider::new(server.leak_url("/token"))
+        };
+        let config = OAuthClientConfig::new("client-id", "http://localhost:8765/callback")
+            .with_insecure_endpoints(true);
+        let client = OAuthClient::new(provider, config).unwrap();
+
+        let result = client.start_device_flow().await;
+        assert!(
+            matches!(
+                &result,
+                Err(OAuthError::OAuthErrorResponse { error, error_description, status: 400, .. })
+                    if error == "invalid_client"
+                        && error_description.as_deref() == Some("unknown client")
+            ),
+            "unexpected result: {result:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn run_device_flow_stops_when_device_code_expires() {
+        let server = MockServer::start(vec![
+            MockResponse::json(
+                200,
+                r#"{"device_code":"device","user_code":"ABCD-EFGH","verification_uri":"https://example.com/device","expires_in":1,"interval":0}"#,
+            ),
+            MockResponse::json(400, r#"{"error":"authorization_pending"}"#),
+            MockResponse::json(400, r#"{"error":"authorization_pending"}"#),
+        ]);
+        let provider = TestProvider {
+            device_authorization_url: Some(server.leak_url("/device")),
+            ..TestProvider::new(server.leak_url("/token"))
+        };
+        let config = OAuthClientConfig::new("client-id", "http://localhost:8765/callback")
+            .with_insecure_endpoints(true);
+        let client = OAuthClient::new(provider, config).unwrap();
+
+        let device = client.start_device_flow().await.unwrap();
+        let result = client
+            .poll_device_token(
+                &device.device_code,
+                Duration::from_millis(400),
+                device.expires_at(),
+            )
+            .await;
+        assert!(matches!(result, Err(OAuthError::DeviceCodeExpired)));
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn slow_down_past_the_expiry_stops_polling() {
+        let server = MockServer::start(vec![MockResponse::json(400, r#"{"error":"slow_down"}"#)]);
+        let config = OAuthClientConfig::new("client-id", "http://localhost:8765/callback")
+            .with_insecure_endpoints(true);
+        let client =
+            OAuthClient::new(TestProvider::new(server.leak_url("/token")), config).unwrap();
+
+        // A 5s slow_down increment overshoots the 3s window, so polling stops
+        // instead of sleeping past expiry.
+        let expires_at = Instant::now() + Duration::from_secs(3);
+        let result = client
+            .poll_device_token("device", Duration::ZERO, expires_at)
+            .await;
+        assert!(matches!(result, Err(OAuthError::DeviceCodeExpired)));
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn slow_down_widens_the_polling_interval() {
+        let server = MockServer::start(vec![
+            MockResponse::json(400, r#"{"error":"slow_down"}"#),
+            MockResponse::json(400, r#"{"error":"authorization_pending"}"#),
+            MockResponse::json(400, r#"{"error":"slow_down"}"#),
+            MockResponse::json(200, r#"{"access_token":"access"}"#),
+        ]);
+        let clock = Arc::new(RecordingClock::default());
+        let config = OAuthClientConfig::new("client-id", "http://localhost:8765/callback")
+            .with_insecure_endpoints(true)
+            .with_clock(clock.clone());
+        let client =
+            OAuthClient::new(TestProvider::new(server.leak_url("/token")), config).unwrap();
+
+        let expires_at = Instant::now() + Duration::from_secs(600);
+        let token = client
+            .poll_device_token("device", Duration::from_secs(1), expires_at)
+            .await
+            .unwrap();
+
+        assert_eq!(token.access_token, "access");
+        let seconds: Vec<u64> = clock
+            .sleeps
+            .lock()
+            .unwrap()
+            .iter()
+            .map(Duration::as_secs)
+            .collect();
+        assert_eq!(seconds, [1, 6, 6, 11]);
+    }
+
+    #[tokio::test]
+    async fn body_logger_masks_the_device_code() {
+        let server = MockServer::start(vec![MockResponse::json(
+            200,
+            r#"{"access_token":"access"}"#,
+        )]);
+        let logged = Arc::new(Mutex::new(Vec::new()));
+        let recorded = logged.clone();
+        let config = OAuthClientConfig::new("client-id", "http://localhost:8765/callback")
+            .with_insecure_endpoints(true)
+            .with_body_logger(Arc::new(move |body: &str| {
+                recorded.lock().unwrap().push(body.to_string())
+            }));
+        let client =
+            OAuthClient::new(TestProvider::new(server.leak_url("/token")), config).unwrap();
+
+        let expires_at = Instant::now() + Duration::from_secs(60);
+        client
+            .poll_device_token("secret-device", Duration::ZERO, expires_at)
+            .await
+            .unwrap();
+
+        let logged = logged.lock().unwrap();
+        assert!(logged[0].contains(r#""device_code":"***""#));
+        assert!(!logged[0].contains("secret-device"));
+    }
+}
@@ -17,22 +17,82 @@ pub enum OAuthError {
     #[error("invalid redirect uri: {0}")]
     InvalidRedirectUri(String),
 
+    #[error("insecure endpoint (https required): {0}")]
+    InsecureEndpoint(String),
+
+    #[error("environment variable {0} is not set")]
+    MissingEnvVar(String),
+
+    #[error("invalid {name}: {message}")]
+    InvalidParam { name: String, message: String },
+
+    #[error("invalid PKCE verifier length {length} (expected 43..=128 characters)")]
+    InvalidPkceLength { length: usize },
+
+    #[error("invalid character {invalid:?} in PKCE verifier")]
+    InvalidPkceChars { invalid: char },
+
+    #[error("invalid jwt: {0}")]
+    InvalidJwt(String),
+
     #[error("invalid header: {name}={value}")]
     InvalidHeader { name: String, value: String },
 
+    #[error("oauth error response (status={status}, error={error})")]
+    OAuthErrorResponse {
+        error: String,
+        error_description: Option<String>,
+        error_uri: Option<String>,
+        status: u16,
+    },
+
+    #[error("token endpoint certificate does not match pinned sha256 {expected}")]
+    CertificatePinMismatch { expected: String },
+
     #[error("http status {status}: {body}")]
     HttpStatus { status: u16, body: String },
 
     #[error("invalid response: {message}")]
     InvalidResponse { message: String, body: String },
 
+    #[error("unsupported operation: {0}")]
+    UnsupportedOperation(String),
+
+    #[error("authorization denied: {error}")]
+    AuthorizationDenied {
+        error: String,
+        error_description: Option<String>,
+    },
+
     #[error("missing authorization code in callback url")]
     MissingAuthorizationCode,
 
+    #[error("redirect uri mismatch (expected={expected}, received={received})")]
+    RedirectUriMismatch { expected: String, received: String },
+
+    #[error("device code expired before authorization completed")]
+    DeviceCodeExpired,
+
     #[error("state mismatch (expected={expected}, received={received})")]
     StateMismatch { expected: String, received: String },
 
+    #[error("id_token nonce mismatch (expected={expected}, received={received:?})")]
+    NonceMismatch {
+        expected: String,
+        received: Option<String>,
+    },
+
+    #[error("state expired (age={age:?}, max_age={max_age:?})")]
+    StateExpired {
+        age: std::time::Duration,
+        max_age: std::time::Duration,
+    },
+
     #[cfg(feature = "local-server")]
     #[error("local server timed out after {timeout:?}")]
     LocalServerTimeout { timeout: std::time::Duration },
+
+    #[cfg(feature = "local-server")]
+    #[error("local server gave up after {attempts} invalid callback requests")]
+    LocalServerAttemptsExhausted { attempts: usize },
 }
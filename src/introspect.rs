@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::client::{HttpReply, status_error};
+use crate::{OAuthClient, OAuthError, OAuthProvider, TokenRequestFormat};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntrospectionResponse {
+    pub active: bool,
+    pub scope: Option<String>,
+    pub client_id: Option<String>,
+    pub username: Option<String>,
+    pub exp: Option<u64>,
+    pub sub: Option<String>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+impl<P: OAuthProvider> OAuthClient<P> {
+    pub async fn introspect_token(&self, token: &str) -> Result<IntrospectionResponse, OAuthError> {
+        let url = self.provider().introspection_url().ok_or_else(|| {
+            OAuthError::UnsupportedOperation(format!(
+                "{} does not support token introspection",
+                self.provider().id()
+            ))
+        })?;
+
+        let mut payload = vec![
+            ("token".to_string(), token.to_string()),
+            ("client_id".to_string(), self.config().client_id.clone()),
+        ];
+        if let Some(secret) = &self.config().client_secret {
+            payload.push(("client_secret".to_string(), secret.clone()));
+        }
+
+        // RFC 7662 §2.1: the request is always form-encoded.
+        let HttpReply { status, body, .. } = self
+            .post_payload_as(url, &payload, TokenRequestFormat::Form)
+            .await?;
+        if !status.is_success() {
+            return Err(status_error(status, body));
+        }
+
+        serde_json::from_str(&body).map_err(|err| OAuthError::InvalidResponse {
+            message: err.to_string(),
+            body,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use crate::test_support::{MockResponse, MockServer, TestProvider};
+    use crate::{OAuthClient, OAuthClientConfig};
+
+    #[tokio::test]
+    async fn introspect_token_parses_known_and_extra_fields() {
+        let server = MockServer::start(vec![MockResponse::json(
+            200,
+            r#"{"active":true,"scope":"read write","client_id":"client-id","username":"jdoe","exp":1999999999,"sub":"user-1","iss":"https://example.com"}"#,
+        )]);
+        let provider = TestProvider {
+            introspection_url: Some(server.leak_url("/introspect")),
+            ..TestProvider::new(server.leak_url("/token"))
+        };
+        let config = OAuthClientConfig::new("client-id", "http://localhost:8765/callback")
+            .with_insecure_endpoints(true);
+        let client = OAuthClient::new(provider, config).unwrap();
+
+        let response = client.introspect_token("access").await.unwrap();
+        assert!(response.active);
+        assert_eq!(response.scope.as_deref(), Some("read write"));
+        assert_eq!(response.username.as_deref(), Some("jdoe"));
+        assert_eq!(response.exp, Some(1_999_999_999));
+        assert_eq!(response.sub.as_deref(), Some("user-1"));
+        assert_eq!(response.extra["iss"], "https://example.com");
+
+        let request = &server.requests()[0];
+        assert_eq!(request.path, "/introspect");
+        assert_eq!(
+            request.header("content-type"),
+            Some("application/x-www-form-urlencoded")
+        );
+        assert_eq!(
+            request.params().get("token").map(String::as_str),
+            Some("access")
+        );
+    }
+
+    #[tokio::test]
+    async fn body_logger_masks_the_introspected_token() {
+        let server = MockServer::start(vec![MockResponse::json(200, r#"{"active":false}"#)]);
+        let logged = Arc::new(Mutex::new(Vec::new()));
+        let recorded = logged.clone();
+        let provider = TestProvider {
+            introspection_url: Some(server.leak_url("/introspect")),
+            ..TestProvider::new(server.leak_url("/token"))
+        };
+        let config = OAuthClientConfig::new("client-id", "http://localhost:8765/callback")
+            .with_insecure_endpoints(true)
+            .with_body_logger(Arc::new(move |body: &str| {
+                recorded.lock().unwrap().push(body.to_string())
+            }));
+        let client = OAuthClient::new(provider, config).unwrap();
+
+        let response = client.introspect_token("secret-access").await.unwrap();
+        assert!(!response.active);
+
+        let logged = logged.lock().unwrap();
+        assert!(logged[0].contains("token=***"));
+        assert!(!logged[0].contains("secret-access"));
+    }
+}
@@ -0,0 +1,61 @@
+use std::collections::HashMap;
+
+use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+
+use crate::OAuthError;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IdTokenClaims {
+    pub sub: Option<String>,
+    pub email: Option<String>,
+    pub name: Option<String>,
+    // `aud` may be a single string or an array of strings.
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub aud: Vec<String>,
+    pub iss: Option<String>,
+    pub exp: Option<u64>,
+    pub nonce: Option<String>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+pub(crate) fn decode_payload(token: &str) -> Option<Value> {
+    decode_claims(token).ok()
+}
+
+pub(crate) fn decode_claims<T: DeserializeOwned>(token: &str) -> Result<T, OAuthError> {
+    let mut segments = token.split('.');
+    let (Some(_header), Some(payload), Some(_signature), None) = (
+        segments.next(),
+        segments.next(),
+        segments.next(),
+        segments.next(),
+    ) else {
+        return Err(OAuthError::InvalidJwt(
+            "expected three dot-separated segments".to_string(),
+        ));
+    };
+
+    let bytes = URL_SAFE_NO_PAD
+        .decode(payload.trim_end_matches('='))
+        .map_err(|err| OAuthError::InvalidJwt(format!("payload is not base64url: {err}")))?;
+    serde_json::from_slice(&bytes)
+        .map_err(|err| OAuthError::InvalidJwt(format!("payload is not valid claims JSON: {err}")))
+}
+
+fn one_or_many<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(value) => vec![value],
+        OneOrMany::Many(values) => values,
+    })
+}
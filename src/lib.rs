@@ -5,17 +5,52 @@
 //! OpenAI, and can be extended to other providers via the `OAuthProvider` trait.
 
 mod client;
+mod clock;
+mod device;
+mod duration_ms;
 mod error;
+mod introspect;
+mod jwt;
 #[cfg(feature = "local-server")]
 mod local_server;
+#[cfg(feature = "tls-pinning")]
+mod pinning;
 mod pkce;
+pub mod prelude;
 mod providers;
+#[cfg(feature = "qr")]
+mod qr;
+mod refresh;
+mod revoke;
+mod state;
+mod store;
+#[cfg(test)]
+mod test_support;
 mod types;
 
-pub use client::{OAuthClient, OAuthClientConfig};
+pub use client::{
+    ClientDescription, OAuthClient, OAuthClientConfig, OAuthClientConfigOverlay, PreExchangeHook,
+};
+pub use clock::{Clock, MockClock, Sleep, SystemClock};
+pub use device::DeviceAuthorizationResponse;
 pub use error::OAuthError;
+pub use introspect::IntrospectionResponse;
+pub use jwt::IdTokenClaims;
+#[cfg(feature = "local-server-tls")]
+pub use local_server::LocalServerTls;
 #[cfg(feature = "local-server")]
-pub use local_server::{LocalServer, LocalServerConfig};
-pub use pkce::PkcePair;
-pub use providers::{AnthropicProvider, OAuthProvider, OpenAIProvider, TokenRequestFormat};
-pub use types::{AuthorizationRequest, AuthorizationResponse, TokenResponse};
+pub use local_server::{LocalServer, LocalServerConfig, RedirectTarget};
+pub use pkce::{PkceMethod, PkcePair, RngFallback};
+#[cfg(feature = "async-provider")]
+pub use providers::DynamicParams;
+pub use providers::{
+    AnthropicProvider, DiscordProvider, DiscoveryProvider, GenericProvider, GitHubProvider,
+    GoogleProvider, OAuthProvider, OpenAIProvider, TokenRequestFormat, TwitchProvider,
+};
+pub use refresh::{ErrorAction, RefreshErrorObserver, RefreshPolicy};
+pub use state::{HmacStateCodec, StateStrategy};
+pub use store::{FileTokenStore, FileTokenStoreGuard, StoredToken, TokenStore};
+pub use types::{
+    AuthorizationRequest, AuthorizationResponse, AuthorizationWarning, BearerChallenge,
+    ExpiryState, OAuthErrorResponse, Prompt, TokenResponse, parse_bearer_challenge,
+};
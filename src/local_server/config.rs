@@ -1,20 +1,70 @@
+use std::fmt;
+use std::sync::Arc;
 use std::time::Duration;
 
+use serde::{Deserialize, Serialize};
+
 use crate::OAuthError;
 
 use super::target::RedirectTarget;
+#[cfg(feature = "local-server-tls")]
+use super::tls::LocalServerTls;
+
+pub(crate) type RequestObserver = Arc<dyn Fn(&str) + Send + Sync>;
 
 pub(crate) const DEFAULT_SUCCESS_HTML: &str = include_str!("html/success.html");
 pub(crate) const DEFAULT_ERROR_HTML: &str = include_str!("html/error.html");
 
-#[derive(Debug, Clone)]
+const COMMON_PORTS: &[(u16, &str)] = &[
+    (
+        3000,
+        "port 3000 is commonly used by Node.js and React dev servers",
+    ),
+    (4200, "port 4200 is commonly used by the Angular dev server"),
+    (
+        5000,
+        "port 5000 is commonly used by Flask and macOS AirPlay Receiver",
+    ),
+    (5173, "port 5173 is commonly used by the Vite dev server"),
+    (
+        8000,
+        "port 8000 is commonly used by Django and Python http.server",
+    ),
+    (
+        8080,
+        "port 8080 is commonly used by HTTP proxies and Java app servers",
+    ),
+    (8888, "port 8888 is commonly used by Jupyter"),
+];
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct LocalServerConfig {
     pub host: String,
     pub port: u16,
     pub path: String,
+    #[serde(default, with = "crate::duration_ms::option")]
     pub timeout: Option<Duration>,
-    pub success_html: String,
-    pub error_html: String,
+    #[serde(default)]
+    pub success_html: Option<String>,
+    #[serde(default)]
+    pub error_html: Option<String>,
+    #[serde(default)]
+    pub denied_html: Option<String>,
+    #[serde(default)]
+    pub not_found_html: Option<String>,
+    #[serde(default)]
+    pub server_error_html: Option<String>,
+    #[serde(default)]
+    pub connection_header: Option<String>,
+    #[serde(skip)]
+    pub expected_state: Option<String>,
+    #[serde(default)]
+    pub max_attempts: Option<usize>,
+    #[serde(skip)]
+    pub request_observer: Option<RequestObserver>,
+    #[cfg(feature = "local-server-tls")]
+    #[serde(skip)]
+    pub tls: Option<LocalServerTls>,
 }
 
 impl LocalServerConfig {
@@ -24,25 +74,69 @@ impl LocalServerConfig {
             port,
             path: normalize_path(path.into()),
             timeout: None,
-            success_html: DEFAULT_SUCCESS_HTML.to_string(),
-            error_html: DEFAULT_ERROR_HTML.to_string(),
+            success_html: None,
+            error_html: None,
+            denied_html: None,
+            not_found_html: None,
+            server_error_html: None,
+            connection_header: None,
+            expected_state: None,
+            max_attempts: None,
+            request_observer: None,
+            #[cfg(feature = "local-server-tls")]
+            tls: None,
         }
     }
 
     pub fn from_redirect_uri(redirect_uri: &str) -> Result<Self, OAuthError> {
-        let target = RedirectTarget::parse(redirect_uri)?;
+        let target = RedirectTarget::parse(redirect_uri, false)?;
         Ok(Self {
             host: target.host,
             port: target.port,
             path: target.path,
             timeout: None,
-            success_html: DEFAULT_SUCCESS_HTML.to_string(),
-            error_html: DEFAULT_ERROR_HTML.to_string(),
+            success_html: None,
+            error_html: None,
+            denied_html: None,
+            not_found_html: None,
+            server_error_html: None,
+            connection_header: None,
+            expected_state: None,
+            max_attempts: None,
+            request_observer: None,
+            #[cfg(feature = "local-server-tls")]
+            tls: None,
         })
     }
 
     pub fn redirect_uri(&self) -> String {
-        format!("http://{}:{}{}", self.host, self.port, self.path)
+        let scheme = if self.uses_tls() { "https" } else { "http" };
+        if self.host.contains(':') && !self.host.starts_with('[') {
+            return format!("{scheme}://[{}]:{}{}", self.host, self.port, self.path);
+        }
+        format!("{scheme}://{}:{}{}", self.host, self.port, self.path)
+    }
+
+    // The scheme, host, port and path the local server will bind and route.
+    pub fn target(&self) -> Result<RedirectTarget, OAuthError> {
+        RedirectTarget::parse(&self.redirect_uri(), self.uses_tls())
+    }
+
+    #[cfg(feature = "local-server-tls")]
+    pub(super) fn uses_tls(&self) -> bool {
+        self.tls.is_some()
+    }
+
+    #[cfg(not(feature = "local-server-tls"))]
+    pub(super) fn uses_tls(&self) -> bool {
+        false
+    }
+
+    pub fn warn_if_common_port(&self) -> Option<&'static str> {
+        COMMON_PORTS
+            .iter()
+            .find(|(port, _)| *port == self.port)
+            .map(|(_, warning)| *warning)
     }
 
     pub fn with_timeout(mut self, timeout: Duration) -> Self {
@@ -51,14 +145,81 @@ impl LocalServerConfig {
     }
 
     pub fn with_success_html(mut self, html: impl Into<String>) -> Self {
-        self.success_html = html.into();
+        self.success_html = Some(html.into());
         self
     }
 
     pub fn with_error_html(mut self, html: impl Into<String>) -> Self {
-        self.error_html = html.into();
+        self.error_html = Some(html.into());
+        self
+    }
+
+    pub fn with_denied_html(mut self, html: impl Into<String>) -> Self {
+        self.denied_html = Some(html.into());
+        self
+    }
+
+    pub fn with_not_found_html(mut self, html: impl Into<String>) -> Self {
+        self.not_found_html = Some(html.into());
+        self
+    }
+
+    pub fn with_server_error_html(mut self, html: impl Into<String>) -> Self {
+        self.server_error_html = Some(html.into());
+        self
+    }
+
+    pub fn with_connection_header(mut self, value: impl Into<String>) -> Self {
+        self.connection_header = Some(value.into());
+        self
+    }
+
+    pub fn with_expected_state(mut self, state: impl Into<String>) -> Self {
+        self.expected_state = Some(state.into());
         self
     }
+
+    pub fn with_max_attempts(mut self, max_attempts: usize) -> Self {
+        self.max_attempts = Some(max_attempts);
+        self
+    }
+
+    pub fn with_request_observer(mut self, observer: Arc<dyn Fn(&str) + Send + Sync>) -> Self {
+        self.request_observer = Some(observer);
+        self
+    }
+
+    // Serves the callback over https; the redirect URI switches scheme to match.
+    #[cfg(feature = "local-server-tls")]
+    pub fn with_tls(mut self, cert_pem: impl Into<String>, key_pem: impl Into<String>) -> Self {
+        self.tls = Some(LocalServerTls {
+            cert_pem: cert_pem.into(),
+            key_pem: key_pem.into(),
+        });
+        self
+    }
+}
+
+impl fmt::Debug for LocalServerConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("LocalServerConfig");
+        debug
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("path", &self.path)
+            .field("timeout", &self.timeout)
+            .field("success_html", &self.success_html)
+            .field("error_html", &self.error_html)
+            .field("denied_html", &self.denied_html)
+            .field("not_found_html", &self.not_found_html)
+            .field("server_error_html", &self.server_error_html)
+            .field("connection_header", &self.connection_header)
+            .field("expected_state", &self.expected_state)
+            .field("max_attempts", &self.max_attempts);
+        #[cfg(feature = "local-server-tls")]
+        debug.field("tls", &self.tls);
+        debug.finish_non_exhaustive()
+    }
 }
 
 fn normalize_path(path: String) -> String {
@@ -73,6 +234,8 @@ fn normalize_path(path: String) -> String {
 
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+
     use super::LocalServerConfig;
 
     #[test]
@@ -80,5 +243,48 @@ mod tests {
         let config = LocalServerConfig::new("localhost", 8765, "callback");
         assert_eq!(config.path, "/callback");
         assert_eq!(config.redirect_uri(), "http://localhost:8765/callback");
+
+        let config = LocalServerConfig::new("::1", 8765, "/callback");
+        assert_eq!(config.redirect_uri(), "http://[::1]:8765/callback");
+    }
+
+    #[test]
+    fn target_exposes_the_parsed_redirect_parts() {
+        let target = LocalServerConfig::new("127.0.0.1", 8765, "oauth/callback")
+            .target()
+            .unwrap();
+        assert_eq!(target.scheme, "http");
+        assert_eq!(target.host, "127.0.0.1");
+        assert_eq!(target.port, 8765);
+        assert_eq!(target.path, "/oauth/callback");
+    }
+
+    #[test]
+    fn local_server_config_round_trips_through_json() {
+        let config = LocalServerConfig::new("127.0.0.1", 8765, "/callback")
+            .with_timeout(Duration::from_secs(90))
+            .with_denied_html("denied");
+        let json = serde_json::to_value(&config).unwrap();
+        assert_eq!(json["timeout"], 90_000);
+
+        let restored: LocalServerConfig = serde_json::from_value(json).unwrap();
+        assert_eq!(restored.redirect_uri(), config.redirect_uri());
+        assert_eq!(restored.timeout, Some(Duration::from_secs(90)));
+        assert_eq!(restored.denied_html.as_deref(), Some("denied"));
+
+        let minimal: LocalServerConfig =
+            serde_json::from_str(r#"{"host":"localhost","port":0,"path":"/cb"}"#).unwrap();
+        assert_eq!(minimal.success_html, None);
+    }
+
+    #[test]
+    fn warns_about_common_dev_ports() {
+        let config = LocalServerConfig::new("localhost", 3000, "/callback");
+        assert!(config.warn_if_common_port().unwrap().contains("3000"));
+
+        let config = LocalServerConfig::new("localhost", 0, "/callback");
+        assert_eq!(config.warn_if_common_port(), None);
+        let config = LocalServerConfig::new("localhost", 51234, "/callback");
+        assert_eq!(config.warn_if_common_port(), None);
     }
 }
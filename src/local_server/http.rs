@@ -1,15 +1,18 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 use axum::{
-    extract::{RawQuery, State},
-    http::StatusCode,
-    response::{Html, IntoResponse},
+    extract::{RawQuery, Request, State},
+    http::{HeaderValue, StatusCode, header::CONNECTION},
+    middleware::Next,
+    response::{Html, IntoResponse, Response},
 };
 use tokio::sync::oneshot;
 
 use crate::{AuthorizationResponse, OAuthError};
 
+use super::config::RequestObserver;
 use super::target::RedirectTarget;
 
 type ResponseResult = Result<AuthorizationResponse, OAuthError>;
@@ -22,15 +25,60 @@ pub(super) struct LocalServerState {
     pub(super) target: RedirectTarget,
     pub(super) success_html: String,
     pub(super) error_html: String,
+    pub(super) denied_html: String,
+    pub(super) not_found_html: String,
+    pub(super) server_error_html: String,
+    pub(super) connection_header: Option<HeaderValue>,
+    pub(super) expected_state: Option<String>,
+    pub(super) strict_state: bool,
+    pub(super) attempts: Option<AttemptBudget>,
+    pub(super) request_observer: Option<RequestObserver>,
     pub(super) response_tx: SharedResponseSender,
 }
 
-pub(super) fn send_response(response_tx: &SharedResponseSender, response: ResponseResult) {
-    if let Ok(mut guard) = response_tx.lock() {
-        if let Some(sender) = guard.take() {
-            let _ = sender.send(response);
+// Counts rejected callbacks; 404s such as `/favicon.ico` never reach it.
+#[derive(Clone)]
+pub(super) struct AttemptBudget {
+    max: usize,
+    failed: Arc<AtomicUsize>,
+}
+
+impl AttemptBudget {
+    pub(super) fn new(max: usize) -> Self {
+        Self {
+            max,
+            failed: Arc::default(),
         }
     }
+
+    fn record_failure(&self) -> Option<OAuthError> {
+        let attempts = self.failed.fetch_add(1, Ordering::SeqCst) + 1;
+        (attempts >= self.max).then_some(OAuthError::LocalServerAttemptsExhausted { attempts })
+    }
+}
+
+// Without a budget the error (if any) ends the listen immediately; with one,
+// only running out of attempts does.
+fn reject(
+    attempts: &Option<AttemptBudget>,
+    response_tx: &SharedResponseSender,
+    error: Option<OAuthError>,
+) {
+    let error = match attempts {
+        Some(budget) => budget.record_failure(),
+        None => error,
+    };
+    if let Some(error) = error {
+        send_response(response_tx, Err(error));
+    }
+}
+
+pub(super) fn send_response(response_tx: &SharedResponseSender, response: ResponseResult) {
+    if let Ok(mut guard) = response_tx.lock()
+        && let Some(sender) = guard.take()
+    {
+        let _ = sender.send(response);
+    }
 }
 
 pub(super) async fn callback_handler(
@@ -41,33 +89,109 @@ pub(super) async fn callback_handler(
         target,
         success_html,
         error_html,
+        denied_html,
+        server_error_html,
+        expected_state,
+        strict_state,
+        attempts,
         response_tx,
+        ..
     } = state;
 
     let query = query.unwrap_or_default();
     let callback_url = match target.build_callback_url(&query) {
         Ok(url) => url,
         Err(error) => {
-            send_response(&response_tx, Err(error));
-            return (StatusCode::INTERNAL_SERVER_ERROR, Html(error_html));
+            reject(&attempts, &response_tx, Some(error));
+            return (StatusCode::INTERNAL_SERVER_ERROR, Html(server_error_html));
         }
     };
 
     match AuthorizationResponse::from_url(&callback_url) {
+        Ok(response)
+            if let Some(expected) = expected_state
+                && !state_matches(&response, &expected, strict_state) =>
+        {
+            let error = OAuthError::StateMismatch {
+                expected,
+                received: response.state.unwrap_or_default(),
+            };
+            reject(&attempts, &response_tx, Some(error));
+            (StatusCode::BAD_REQUEST, Html(error_html))
+        }
         Ok(response) => {
             send_response(&response_tx, Ok(response));
             (StatusCode::OK, Html(success_html))
         }
-        Err(OAuthError::MissingAuthorizationCode) => (StatusCode::BAD_REQUEST, Html(error_html)),
-        Err(error) => {
+        Err(error @ OAuthError::AuthorizationDenied { .. }) => {
             send_response(&response_tx, Err(error));
-            (StatusCode::INTERNAL_SERVER_ERROR, Html(error_html))
+            (StatusCode::BAD_REQUEST, Html(denied_html))
+        }
+        Err(OAuthError::MissingAuthorizationCode) => {
+            reject(&attempts, &response_tx, None);
+            (StatusCode::BAD_REQUEST, Html(error_html))
+        }
+        Err(error) => {
+            reject(&attempts, &response_tx, Some(error));
+            (StatusCode::INTERNAL_SERVER_ERROR, Html(server_error_html))
         }
     }
 }
 
+pub(super) async fn observe_request(
+    State(state): State<LocalServerState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if let Some(observer) = &state.request_observer {
+        let uri = request.uri();
+        let target = match uri.query() {
+            Some(query) => format!("{}?{}", uri.path(), redact_code(query)),
+            None => uri.path().to_string(),
+        };
+        observer(&format!(
+            "{} {} {:?}",
+            request.method(),
+            target,
+            request.version()
+        ));
+    }
+    next.run(request).await
+}
+
+pub(super) async fn apply_connection_header(
+    State(state): State<LocalServerState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let mut response = next.run(request).await;
+    if let Some(value) = state.connection_header {
+        response.headers_mut().insert(CONNECTION, value);
+    }
+    response
+}
+
+fn redact_code(query: &str) -> String {
+    query
+        .split('&')
+        .map(|pair| {
+            if pair.starts_with("code=") {
+                "code=***"
+            } else {
+                pair
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+fn state_matches(response: &AuthorizationResponse, expected: &str, strict: bool) -> bool {
+    response.state.as_deref() == Some(expected)
+        || (!strict && response.fragment_state.as_deref() == Some(expected))
+}
+
 pub(super) async fn fallback_handler(State(state): State<LocalServerState>) -> impl IntoResponse {
-    (StatusCode::NOT_FOUND, Html(state.error_html))
+    (StatusCode::NOT_FOUND, Html(state.not_found_html))
 }
 
 pub(super) async fn wait_for_response(
@@ -2,6 +2,11 @@ mod config;
 mod http;
 mod server;
 mod target;
+#[cfg(feature = "local-server-tls")]
+mod tls;
 
 pub use config::LocalServerConfig;
 pub use server::LocalServer;
+pub use target::RedirectTarget;
+#[cfg(feature = "local-server-tls")]
+pub use tls::LocalServerTls;
@@ -1,57 +1,100 @@
-use std::net::TcpListener;
+use std::fmt;
+use std::future::{Future, IntoFuture};
+use std::io;
+use std::net::{SocketAddr, TcpListener};
+use std::pin::Pin;
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::Duration;
 
-use axum::{Router, routing::get};
+use axum::http::{HeaderValue, header::CONNECTION};
+use axum::{Router, middleware, routing::get};
 use tokio::net::TcpListener as TokioTcpListener;
 use tokio::runtime::Builder;
 use tokio::sync::oneshot;
 
 use crate::{AuthorizationResponse, OAuthError};
 
-use super::config::{DEFAULT_ERROR_HTML, DEFAULT_SUCCESS_HTML, LocalServerConfig};
+use super::config::{DEFAULT_ERROR_HTML, DEFAULT_SUCCESS_HTML, LocalServerConfig, RequestObserver};
 use super::http::{
-    LocalServerState, callback_handler, fallback_handler, send_response, wait_for_response,
+    AttemptBudget, LocalServerState, apply_connection_header, callback_handler, fallback_handler,
+    observe_request, send_response, wait_for_response,
 };
 use super::target::RedirectTarget;
+#[cfg(feature = "local-server-tls")]
+use super::tls::{LocalServerTls, TlsListener};
 
-#[derive(Debug, Clone)]
+type ServeFuture = Pin<Box<dyn Future<Output = io::Result<()>> + Send>>;
+
+#[derive(Clone)]
 pub struct LocalServer {
     target: RedirectTarget,
-    success_html: String,
-    error_html: String,
+    success_html: Option<String>,
+    error_html: Option<String>,
+    denied_html: Option<String>,
+    not_found_html: Option<String>,
+    server_error_html: Option<String>,
+    connection_header: Option<String>,
+    expected_state: Option<String>,
+    strict_state: bool,
+    max_attempts: Option<usize>,
     timeout: Option<Duration>,
+    request_observer: Option<RequestObserver>,
+    local_addr: Arc<Mutex<Option<SocketAddr>>>,
+    #[cfg(feature = "local-server-tls")]
+    tls: Option<LocalServerTls>,
 }
 
 impl LocalServer {
     pub fn new(redirect_uri: impl Into<String>) -> Result<Self, OAuthError> {
         let redirect_uri = redirect_uri.into();
         Ok(Self {
-            target: RedirectTarget::parse(&redirect_uri)?,
-            success_html: DEFAULT_SUCCESS_HTML.to_string(),
-            error_html: DEFAULT_ERROR_HTML.to_string(),
+            target: RedirectTarget::parse(&redirect_uri, false)?,
+            success_html: None,
+            error_html: None,
+            denied_html: None,
+            not_found_html: None,
+            server_error_html: None,
+            connection_header: None,
+            expected_state: None,
+            strict_state: false,
+            max_attempts: None,
             timeout: None,
+            request_observer: None,
+            local_addr: Arc::default(),
+            #[cfg(feature = "local-server-tls")]
+            tls: None,
         })
     }
 
     pub fn from_config(config: LocalServerConfig) -> Result<Self, OAuthError> {
         let redirect_uri = config.redirect_uri();
         Ok(Self {
-            target: RedirectTarget::parse(&redirect_uri)?,
+            target: RedirectTarget::parse(&redirect_uri, config.uses_tls())?,
             success_html: config.success_html,
             error_html: config.error_html,
+            denied_html: config.denied_html,
+            not_found_html: config.not_found_html,
+            server_error_html: config.server_error_html,
+            connection_header: config.connection_header,
+            expected_state: config.expected_state,
+            strict_state: false,
+            max_attempts: config.max_attempts,
             timeout: config.timeout,
+            request_observer: config.request_observer,
+            local_addr: Arc::default(),
+            #[cfg(feature = "local-server-tls")]
+            tls: config.tls,
         })
     }
 
     pub fn with_success_html(mut self, html: impl Into<String>) -> Self {
-        self.success_html = html.into();
+        self.success_html = Some(html.into());
         self
     }
 
     pub fn with_error_html(mut self, html: impl Into<String>) -> Self {
-        self.error_html = html.into();
+        self.error_html = Some(html.into());
         self
     }
 
@@ -60,8 +103,51 @@ impl LocalServer {
         self
     }
 
+    // Callbacks carrying any other state get the error page and end the
+    // listen with `StateMismatch`.
+    pub fn with_expected_state(mut self, state: impl Into<String>) -> Self {
+        self.expected_state = Some(state.into());
+        self
+    }
+
+    // Only the query `state` is compared against the expected state; a state
+    // carried in the code's fragment is ignored.
+    pub fn with_strict_state(mut self, strict: bool) -> Self {
+        self.strict_state = strict;
+        self
+    }
+
+    // Keeps serving through up to `max_attempts` rejected callbacks (missing
+    // code, bad state, malformed query) before failing with
+    // `LocalServerAttemptsExhausted`.
+    pub fn with_max_attempts(mut self, max_attempts: usize) -> Self {
+        self.max_attempts = Some(max_attempts);
+        self
+    }
+
+    pub fn with_request_observer(mut self, observer: Arc<dyn Fn(&str) + Send + Sync>) -> Self {
+        self.request_observer = Some(observer);
+        self
+    }
+
+    // A redirect URI with port 0 lets the OS pick a free port; read it back
+    // through `local_addr` or `redirect_uri` once bound.
     pub fn bind(&self) -> Result<TcpListener, OAuthError> {
-        TcpListener::bind((self.target.host.as_str(), self.target.port)).map_err(OAuthError::from)
+        let listener = self.target.bind()?;
+        *self.local_addr.lock().unwrap() = Some(listener.local_addr()?);
+        Ok(listener)
+    }
+
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        *self.local_addr.lock().unwrap()
+    }
+
+    pub fn redirect_uri(&self) -> Result<String, OAuthError> {
+        let mut target = self.target.clone();
+        if let Some(addr) = self.local_addr() {
+            target.port = addr.port();
+        }
+        target.build_callback_url("")
     }
 
     pub fn listen_with(&self, listener: TcpListener) -> Result<AuthorizationResponse, OAuthError> {
@@ -85,33 +171,74 @@ impl LocalServer {
         self.listen_with(listener)
     }
 
+    pub fn listen_with_page(
+        &self,
+        listener: TcpListener,
+    ) -> Result<(AuthorizationResponse, String), OAuthError> {
+        let response = self.listen_with(listener)?;
+        Ok((response, self.success_page()))
+    }
+
+    pub fn listen_once_with_page(&self) -> Result<(AuthorizationResponse, String), OAuthError> {
+        let listener = self.bind()?;
+        self.listen_with_page(listener)
+    }
+
     pub async fn listen_with_async(
         &self,
         listener: TcpListener,
     ) -> Result<AuthorizationResponse, OAuthError> {
+        let connection_header = self
+            .connection_header
+            .as_deref()
+            .map(|value| {
+                HeaderValue::from_str(value).map_err(|_| OAuthError::InvalidHeader {
+                    name: CONNECTION.to_string(),
+                    value: value.to_string(),
+                })
+            })
+            .transpose()?;
         let (response_tx, response_rx) =
             oneshot::channel::<Result<AuthorizationResponse, OAuthError>>();
         let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
         let response_tx = Arc::new(Mutex::new(Some(response_tx)));
 
+        let mut target = self.target.clone();
+        target.port = listener.local_addr()?.port();
         let state = LocalServerState {
-            target: self.target.clone(),
-            success_html: self.success_html.clone(),
-            error_html: self.error_html.clone(),
+            target,
+            success_html: self.success_page(),
+            error_html: self.error_page(),
+            denied_html: self.page_or_error(&self.denied_html),
+            not_found_html: self.page_or_error(&self.not_found_html),
+            server_error_html: self.page_or_error(&self.server_error_html),
+            connection_header,
+            expected_state: self.expected_state.clone(),
+            strict_state: self.strict_state,
+            attempts: self.max_attempts.map(AttemptBudget::new),
+            request_observer: self.request_observer.clone(),
             response_tx: response_tx.clone(),
         };
 
         let app = Router::new()
             .route(&state.target.path, get(callback_handler))
             .fallback(fallback_handler)
+            .layer(middleware::from_fn_with_state(
+                state.clone(),
+                apply_connection_header,
+            ))
+            .layer(middleware::from_fn_with_state(
+                state.clone(),
+                observe_request,
+            ))
             .with_state(state);
 
         listener.set_nonblocking(true)?;
         let listener = TokioTcpListener::from_std(listener)?;
 
-        let server = axum::serve(listener, app).with_graceful_shutdown(async move {
+        let server = self.serve(listener, app, async move {
             let _ = shutdown_rx.await;
-        });
+        })?;
 
         let response_tx_for_server = response_tx.clone();
         let server_handle = tokio::spawn(async move {
@@ -136,4 +263,346 @@ impl LocalServer {
         let listener = self.bind()?;
         self.listen_with_async(listener).await
     }
+
+    fn serve(
+        &self,
+        listener: TokioTcpListener,
+        app: Router,
+        shutdown: impl Future<Output = ()> + Send + 'static,
+    ) -> Result<ServeFuture, OAuthError> {
+        #[cfg(feature = "local-server-tls")]
+        if let Some(tls) = &self.tls {
+            let listener = TlsListener::new(listener, tls.acceptor()?);
+            let server = axum::serve(listener, app).with_graceful_shutdown(shutdown);
+            return Ok(Box::pin(server.into_future()));
+        }
+        let server = axum::serve(listener, app).with_graceful_shutdown(shutdown);
+        Ok(Box::pin(server.into_future()))
+    }
+
+    pub(crate) fn with_fallback_pages(
+        mut self,
+        success_html: Option<&str>,
+        error_html: Option<&str>,
+    ) -> Self {
+        // Pages set on the server or its config win over the provider's.
+        self.success_html = self.success_html.or(success_html.map(str::to_string));
+        self.error_html = self.error_html.or(error_html.map(str::to_string));
+        self
+    }
+
+    fn success_page(&self) -> String {
+        self.success_html
+            .clone()
+            .unwrap_or_else(|| DEFAULT_SUCCESS_HTML.to_string())
+    }
+
+    fn error_page(&self) -> String {
+        self.error_html
+            .clone()
+            .unwrap_or_else(|| DEFAULT_ERROR_HTML.to_string())
+    }
+
+    fn page_or_error(&self, page: &Option<String>) -> String {
+        page.clone().unwrap_or_else(|| self.error_page())
+    }
+}
+
+impl fmt::Debug for LocalServer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LocalServer")
+            .field("target", &self.target)
+            .field("success_html", &self.success_html)
+            .field("error_html", &self.error_html)
+            .field("denied_html", &self.denied_html)
+            .field("not_found_html", &self.not_found_html)
+            .field("server_error_html", &self.server_error_html)
+            .field("connection_header", &self.connection_header)
+            .field("expected_state", &self.expected_state)
+            .field("strict_state", &self.strict_state)
+            .field("max_attempts", &self.max_attempts)
+            .field("timeout", &self.timeout)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::LocalServer;
+    use crate::local_server::config::DEFAULT_SUCCESS_HTML;
+    use crate::{LocalServerConfig, OAuthError};
+
+    #[tokio::test]
+    async fn request_observer_sees_unmatched_paths() {
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let recorded = lines.clone();
+        let config = LocalServerConfig::new("127.0.0.1", 0, "/callback").with_request_observer(
+            Arc::new(move |line: &str| recorded.lock().unwrap().push(line.to_string())),
+        );
+        let server = LocalServer::from_config(config).unwrap();
+        let listener = server.bind().unwrap();
+        let addr = listener.local_addr().unwrap();
+        let handle = tokio::spawn(async move { server.listen_with_async(listener).await });
+
+        let http = reqwest::Client::new();
+        let missing = http
+            .get(format!("http://{addr}/favicon.ico"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(missing.status(), 404);
+        http.get(format!("http://{addr}/callback?code=secret&state=xyz"))
+            .send()
+            .await
+            .unwrap();
+
+        let response = handle.await.unwrap().unwrap();
+        assert_eq!(response.code, "secret");
+        assert_eq!(
+            *lines.lock().unwrap(),
+            vec![
+                "GET /favicon.ico HTTP/1.1".to_string(),
+                "GET /callback?code=***&state=xyz HTTP/1.1".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn port_zero_binds_an_ephemeral_port() {
+        let server = LocalServer::new("http://127.0.0.1:0/callback").unwrap();
+        assert_eq!(server.local_addr(), None);
+
+        let listener = server.bind().unwrap();
+        let port = listener.local_addr().unwrap().port();
+        assert_ne!(port, 0);
+        assert_eq!(server.local_addr().unwrap().port(), port);
+        assert_eq!(
+            server.redirect_uri().unwrap(),
+            format!("http://127.0.0.1:{port}/callback")
+        );
+    }
+
+    #[test]
+    fn configured_pages_win_over_provider_pages() {
+        let server = LocalServer::new("http://127.0.0.1:0/callback").unwrap();
+        let provider = server.clone().with_fallback_pages(Some("provider"), None);
+        assert_eq!(provider.success_page(), "provider");
+
+        let explicit = server
+            .with_success_html(DEFAULT_SUCCESS_HTML)
+            .with_fallback_pages(Some("provider"), Some("provider error"));
+        assert_eq!(explicit.success_page(), DEFAULT_SUCCESS_HTML);
+        assert_eq!(explicit.error_page(), "provider error");
+    }
+
+    #[test]
+    fn dropped_listener_releases_port_for_rebinding() {
+        let server = LocalServer::new("http://127.0.0.1:0/callback").unwrap();
+        let listener = server.bind().unwrap();
+        let port = listener.local_addr().unwrap().port();
+        drop(listener);
+
+        let server = LocalServer::new(format!("http://127.0.0.1:{port}/callback")).unwrap();
+        let listener = server.bind().unwrap();
+        assert_eq!(listener.local_addr().unwrap().port(), port);
+    }
+
+    #[tokio::test]
+    async fn listen_with_page_returns_success_html() {
+        let config = LocalServerConfig::new("127.0.0.1", 0, "/callback")
+            .with_success_html("<p>all done</p>");
+        let server = LocalServer::from_config(config).unwrap();
+        let listener = server.bind().unwrap();
+        let addr = listener.local_addr().unwrap();
+        let handle = tokio::task::spawn_blocking(move || server.listen_with_page(listener));
+
+        let page = reqwest::get(format!("http://{addr}/callback?code=abc"))
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+
+        let (response, html) = handle.await.unwrap().unwrap();
+        assert_eq!(response.code, "abc");
+        assert_eq!(html, "<p>all done</p>");
+        assert_eq!(html, page);
+    }
+
+    #[tokio::test]
+    async fn error_cases_render_their_specific_pages() {
+        let config = LocalServerConfig::new("127.0.0.1", 0, "/callback")
+            .with_error_html("generic")
+            .with_denied_html("denied")
+            .with_not_found_html("not found");
+        let server = LocalServer::from_config(config).unwrap();
+        assert_eq!(server.page_or_error(&server.server_error_html), "generic");
+
+        let listener = server.bind().unwrap();
+        let addr = listener.local_addr().unwrap();
+        let handle = tokio::spawn(async move { server.listen_with_async(listener).await });
+
+        let missing = reqwest::get(format!("http://{addr}/elsewhere"))
+            .await
+            .unwrap();
+        assert_eq!(missing.status(), 404);
+        assert_eq!(missing.text().await.unwrap(), "not found");
+
+        let no_code = reqwest::get(format!("http://{addr}/callback"))
+            .await
+            .unwrap();
+        assert_eq!(no_code.text().await.unwrap(), "generic");
+
+        let denied = reqwest::get(format!(
+            "http://{addr}/callback?error=access_denied&error_description=nope"
+        ))
+        .await
+        .unwrap();
+        assert_eq!(denied.status(), 400);
+        assert_eq!(denied.text().await.unwrap(), "denied");
+
+        let result = handle.await.unwrap();
+        let Err(OAuthError::AuthorizationDenied {
+            error,
+            error_description,
+        }) = result
+        else {
+            panic!("expected AuthorizationDenied, got {result:?}");
+        };
+        assert_eq!(error, "access_denied");
+        assert_eq!(error_description.as_deref(), Some("nope"));
+    }
+
+    #[cfg(feature = "local-server-tls")]
+    #[tokio::test]
+    async fn tls_server_accepts_https_callbacks() {
+        let config = LocalServerConfig::new("127.0.0.1", 0, "/callback").with_tls(
+            include_str!("../../tests/fixtures/localhost.pem"),
+            include_str!("../../tests/fixtures/localhost.key"),
+        );
+        assert!(config.redirect_uri().starts_with("https://"));
+        let server = LocalServer::from_config(config).unwrap();
+        let listener = server.bind().unwrap();
+        let addr = listener.local_addr().unwrap();
+        let handle = tokio::spawn(async move { server.listen_with_async(listener).await });
+
+        let client = reqwest::Client::builder()
+            .tls_danger_accept_invalid_certs(true)
+            .build()
+            .unwrap();
+        assert!(
+            reqwest::get(format!("http://{addr}/callback?code=plain"))
+                .await
+                .is_err()
+        );
+        let page = client
+            .get(format!("https://{addr}/callback?code=abc"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(page.status(), 200);
+
+        let response = handle.await.unwrap().unwrap();
+        assert_eq!(response.code, "abc");
+    }
+
+    #[tokio::test]
+    async fn mismatched_state_is_rejected_by_the_server() {
+        let config = LocalServerConfig::new("127.0.0.1", 0, "/callback")
+            .with_error_html("forged")
+            .with_expected_state("expected");
+        let server = LocalServer::from_config(config).unwrap();
+        let listener = server.bind().unwrap();
+        let addr = listener.local_addr().unwrap();
+        let handle = tokio::spawn(async move { server.listen_with_async(listener).await });
+
+        let page = reqwest::get(format!("http://{addr}/callback?code=abc&state=forged"))
+            .await
+            .unwrap();
+        assert_eq!(page.status(), 400);
+        assert_eq!(page.text().await.unwrap(), "forged");
+
+        let result = handle.await.unwrap();
+        assert!(
+            matches!(&result, Err(OAuthError::StateMismatch { received, .. }) if received == "forged"),
+            "unexpected result: {result:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn strict_state_ignores_the_fragment_state() {
+        let server = LocalServer::new("http://127.0.0.1:0/callback")
+            .unwrap()
+            .with_error_html("forged")
+            .with_expected_state("expected")
+            .with_strict_state(true);
+        let listener = server.bind().unwrap();
+        let addr = listener.local_addr().unwrap();
+        let handle = tokio::spawn(async move { server.listen_with_async(listener).await });
+
+        let page = reqwest::get(format!(
+            "http://{addr}/callback?code=abc%23expected&state=forged"
+        ))
+        .await
+        .unwrap();
+        assert_eq!(page.status(), 400);
+        assert_eq!(page.text().await.unwrap(), "forged");
+
+        let result = handle.await.unwrap();
+        assert!(
+            matches!(&result, Err(OAuthError::StateMismatch { received, .. }) if received == "forged"),
+            "unexpected result: {result:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn bad_callbacks_count_against_max_attempts() {
+        let config = LocalServerConfig::new("127.0.0.1", 0, "/callback")
+            .with_expected_state("expected")
+            .with_max_attempts(2);
+        let server = LocalServer::from_config(config).unwrap();
+        let listener = server.bind().unwrap();
+        let addr = listener.local_addr().unwrap();
+        let handle = tokio::spawn(async move { server.listen_with_async(listener).await });
+
+        let favicon = reqwest::get(format!("http://{addr}/favicon.ico"))
+            .await
+            .unwrap();
+        assert_eq!(favicon.status(), 404);
+        let forged = reqwest::get(format!("http://{addr}/callback?code=abc&state=forged"))
+            .await
+            .unwrap();
+        assert_eq!(forged.status(), 400);
+        assert!(!handle.is_finished());
+
+        reqwest::get(format!("http://{addr}/callback"))
+            .await
+            .unwrap();
+        let result = handle.await.unwrap();
+        assert!(
+            matches!(
+                result,
+                Err(OAuthError::LocalServerAttemptsExhausted { attempts: 2 })
+            ),
+            "unexpected result: {result:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn configured_connection_header_is_sent() {
+        let config = LocalServerConfig::new("127.0.0.1", 0, "/callback")
+            .with_connection_header("keep-alive");
+        let server = LocalServer::from_config(config).unwrap();
+        let listener = server.bind().unwrap();
+        let addr = listener.local_addr().unwrap();
+        let handle = tokio::spawn(async move { server.listen_with_async(listener).await });
+
+        let response = reqwest::get(format!("http://{addr}/callback?code=abc"))
+            .await
+            .unwrap();
+        assert_eq!(response.headers().get("connection").unwrap(), "keep-alive");
+        handle.await.unwrap().unwrap();
+    }
 }
@@ -1,27 +1,36 @@
-use url::Url;
+use std::net::{IpAddr, SocketAddr, TcpListener};
+
+use url::{Host, Url};
 
 use crate::OAuthError;
 
-#[derive(Debug, Clone)]
-pub(super) struct RedirectTarget {
-    pub(super) scheme: String,
-    pub(super) host: String,
-    pub(super) port: u16,
-    pub(super) path: String,
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RedirectTarget {
+    pub scheme: String,
+    pub host: String,
+    pub(super) ip: Option<IpAddr>,
+    pub port: u16,
+    pub path: String,
 }
 
 impl RedirectTarget {
-    pub(super) fn parse(redirect_uri: &str) -> Result<Self, OAuthError> {
+    pub(super) fn parse(redirect_uri: &str, tls: bool) -> Result<Self, OAuthError> {
         let url = Url::parse(redirect_uri)?;
-        if url.scheme() != "http" {
-            return Err(OAuthError::InvalidRedirectUri(
-                "redirect uri must use http scheme".to_string(),
-            ));
+        let scheme = if tls { "https" } else { "http" };
+        if url.scheme() != scheme {
+            return Err(OAuthError::InvalidRedirectUri(format!(
+                "redirect uri must use {scheme} scheme"
+            )));
         }
 
         let host = url.host_str().ok_or_else(|| {
             OAuthError::InvalidRedirectUri("redirect uri is missing host".to_string())
         })?;
+        let ip = match url.host() {
+            Some(Host::Ipv4(ip)) => Some(IpAddr::V4(ip)),
+            Some(Host::Ipv6(ip)) => Some(IpAddr::V6(ip)),
+            _ => None,
+        };
 
         let port = url.port_or_known_default().ok_or_else(|| {
             OAuthError::InvalidRedirectUri("redirect uri is missing port".to_string())
@@ -30,11 +39,21 @@ impl RedirectTarget {
         Ok(Self {
             scheme: url.scheme().to_string(),
             host: host.to_string(),
+            ip,
             port,
             path: url.path().to_string(),
         })
     }
 
+    // `host` keeps the brackets of an IPv6 literal for URL building, so
+    // literals bind through the parsed address instead.
+    pub(super) fn bind(&self) -> std::io::Result<TcpListener> {
+        match self.ip {
+            Some(ip) => TcpListener::bind(SocketAddr::new(ip, self.port)),
+            None => TcpListener::bind((self.host.as_str(), self.port)),
+        }
+    }
+
     pub(super) fn build_callback_url(&self, query: &str) -> Result<String, OAuthError> {
         let base = format!("{}://{}:{}{}", self.scheme, self.host, self.port, self.path);
 
@@ -49,13 +68,30 @@ impl RedirectTarget {
 
 #[cfg(test)]
 mod tests {
+    use std::net::Ipv6Addr;
+
     use super::RedirectTarget;
 
     #[test]
     fn parses_redirect_target() {
-        let target = RedirectTarget::parse("http://localhost:8765/callback").unwrap();
+        let target = RedirectTarget::parse("http://localhost:8765/callback", false).unwrap();
         assert_eq!(target.host, "localhost");
         assert_eq!(target.port, 8765);
         assert_eq!(target.path, "/callback");
+
+        assert!(RedirectTarget::parse("https://localhost:8765/callback", false).is_err());
+        let target = RedirectTarget::parse("https://localhost:8765/callback", true).unwrap();
+        assert_eq!(target.scheme, "https");
+    }
+
+    #[test]
+    fn parses_ipv6_loopback_target() {
+        let target = RedirectTarget::parse("http://[::1]:8765/callback", false).unwrap();
+        assert_eq!(target.ip, Some(Ipv6Addr::LOCALHOST.into()));
+        assert_eq!(target.port, 8765);
+        assert_eq!(
+            target.build_callback_url("code=abc").unwrap(),
+            "http://[::1]:8765/callback?code=abc"
+        );
     }
 }
@@ -1,7 +1,8 @@
 use ai_connect::{
-    AnthropicProvider, OAuthClient, OAuthClientConfig, OAuthError, OAuthProvider, OpenAIProvider,
+    AnthropicProvider, OAuthClient, OAuthClientConfig, OAuthError, OAuthErrorResponse,
+    OAuthProvider, OpenAIProvider, TokenResponse,
 };
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 
 #[derive(Debug, Parser)]
 #[command(
@@ -9,6 +10,9 @@ use clap::{Parser, Subcommand};
     about = "Connect to AI provider accounts via OAuth and print access tokens as JSON."
 )]
 struct Cli {
+    #[arg(long, value_enum, default_value_t = OutputFormat::Json, global = true)]
+    format: OutputFormat,
+
     #[command(subcommand)]
     command: Command,
 }
@@ -17,26 +21,92 @@ struct Cli {
 enum Command {
     Anthropic,
     Openai,
+    /// Print the authorization URL plus the verifier and state to persist,
+    /// without starting a server or opening a browser.
+    AuthorizeUrl {
+        #[arg(value_enum)]
+        provider: ProviderKind,
+    },
+    /// Exchange a pasted redirect URL using a persisted verifier and state.
+    Exchange {
+        #[arg(value_enum)]
+        provider: ProviderKind,
+        #[arg(long)]
+        redirect_url: String,
+        #[arg(long)]
+        verifier: String,
+        #[arg(long)]
+        state: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ProviderKind {
+    Anthropic,
+    Openai,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    Json,
+    Env,
 }
 
 #[tokio::main]
 async fn main() -> Result<(), OAuthError> {
     let cli = Cli::parse();
     match cli.command {
-        Command::Anthropic => run_anthropic().await,
-        Command::Openai => run_openai().await,
+        Command::Anthropic => run_anthropic(cli.format).await,
+        Command::Openai => run_openai(cli.format).await,
+        Command::AuthorizeUrl { provider } => match provider {
+            ProviderKind::Anthropic => print_authorize_url(&anthropic_client()?, cli.format),
+            ProviderKind::Openai => print_authorize_url(&openai_client()?, cli.format),
+        },
+        Command::Exchange {
+            provider,
+            redirect_url,
+            verifier,
+            state,
+        } => {
+            let state = state.as_deref();
+            match provider {
+                ProviderKind::Anthropic => {
+                    let client = anthropic_client()?;
+                    let tokens = exchange(&client, &redirect_url, &verifier, state).await?;
+                    print_tokens(&tokens, cli.format, "ANTHROPIC")
+                }
+                ProviderKind::Openai => {
+                    let client = openai_client()?;
+                    let tokens = exchange(&client, &redirect_url, &verifier, state).await?;
+                    print_tokens(&tokens, cli.format, "OPENAI")
+                }
+            }
+        }
     }
 }
 
-async fn run_anthropic() -> Result<(), OAuthError> {
+fn anthropic_client() -> Result<OAuthClient<AnthropicProvider>, OAuthError> {
     let provider = AnthropicProvider;
     let config = OAuthClientConfig::new(
         AnthropicProvider::default_client_id(),
         AnthropicProvider::default_redirect_uri(),
     )
     .with_scope(provider.default_scope());
+    OAuthClient::new(provider, config)
+}
+
+fn openai_client() -> Result<OAuthClient<OpenAIProvider>, OAuthError> {
+    let provider = OpenAIProvider::new();
+    let config = OAuthClientConfig::new(
+        OpenAIProvider::default_client_id(),
+        OpenAIProvider::default_redirect_uri(),
+    )
+    .with_scope(provider.default_scope());
+    OAuthClient::new(provider, config)
+}
 
-    let client = OAuthClient::new(provider, config)?;
+async fn run_anthropic(format: OutputFormat) -> Result<(), OAuthError> {
+    let client = anthropic_client()?;
 
     let tokens = client
         .run_local_flow(|auth| {
@@ -46,27 +116,14 @@ async fn run_anthropic() -> Result<(), OAuthError> {
             }
             Ok(())
         })
-        .await?;
-
-    let output =
-        serde_json::to_string_pretty(&tokens).map_err(|err| OAuthError::InvalidResponse {
-            message: err.to_string(),
-            body: String::new(),
-        })?;
+        .await
+        .inspect_err(|err| print_error_hint(client.provider(), err))?;
 
-    println!("{output}");
-    Ok(())
+    print_tokens(&tokens, format, "ANTHROPIC")
 }
 
-async fn run_openai() -> Result<(), OAuthError> {
-    let provider = OpenAIProvider::new();
-    let config = OAuthClientConfig::new(
-        OpenAIProvider::default_client_id(),
-        OpenAIProvider::default_redirect_uri(),
-    )
-    .with_scope(provider.default_scope());
-
-    let client = OAuthClient::new(provider, config)?;
+async fn run_openai(format: OutputFormat) -> Result<(), OAuthError> {
+    let client = openai_client()?;
 
     let tokens = client
         .run_local_flow(|auth| {
@@ -76,14 +133,136 @@ async fn run_openai() -> Result<(), OAuthError> {
             }
             Ok(())
         })
-        .await?;
+        .await
+        .inspect_err(|err| print_error_hint(client.provider(), err))?;
 
-    let output =
-        serde_json::to_string_pretty(&tokens).map_err(|err| OAuthError::InvalidResponse {
-            message: err.to_string(),
-            body: String::new(),
-        })?;
+    print_tokens(&tokens, format, "OPENAI")
+}
 
-    println!("{output}");
+fn print_authorize_url<P: OAuthProvider>(
+    client: &OAuthClient<P>,
+    format: OutputFormat,
+) -> Result<(), OAuthError> {
+    let auth = client.authorization_url()?;
+    match format {
+        OutputFormat::Json => {
+            let output = serde_json::json!({
+                "authorization_url": auth.authorization_url,
+                "code_verifier": auth.pkce.code_verifier,
+                "state": auth.state,
+            });
+            println!("{output:#}");
+        }
+        OutputFormat::Env => {
+            println!("AUTHORIZATION_URL={}", auth.authorization_url);
+            println!("CODE_VERIFIER={}", auth.pkce.code_verifier);
+            println!("STATE={}", auth.state);
+        }
+    }
     Ok(())
 }
+
+async fn exchange<P: OAuthProvider>(
+    client: &OAuthClient<P>,
+    redirect_url: &str,
+    verifier: &str,
+    state: Option<&str>,
+) -> Result<TokenResponse, OAuthError> {
+    client
+        .exchange_from_redirect_url(redirect_url, verifier, state)
+        .await
+        .inspect_err(|err| print_error_hint(client.provider(), err))
+}
+
+fn print_error_hint(provider: &impl OAuthProvider, err: &OAuthError) {
+    let OAuthError::OAuthErrorResponse {
+        error,
+        error_description,
+        error_uri,
+        ..
+    } = err
+    else {
+        return;
+    };
+    let response = OAuthErrorResponse {
+        error: error.clone(),
+        error_description: error_description.clone(),
+        error_uri: error_uri.clone(),
+    };
+    if let Some(hint) = provider.interpret_error(&response) {
+        eprintln!("Hint: {hint}");
+    }
+}
+
+fn print_tokens(
+    tokens: &TokenResponse,
+    format: OutputFormat,
+    env_prefix: &str,
+) -> Result<(), OAuthError> {
+    match format {
+        OutputFormat::Json => {
+            let output = serde_json::to_string_pretty(tokens).map_err(|err| {
+                OAuthError::InvalidResponse {
+                    message: err.to_string(),
+                    body: String::new(),
+                }
+            })?;
+            println!("{output}");
+        }
+        OutputFormat::Env => {
+            for (key, value) in tokens.to_env_pairs(env_prefix) {
+                println!("{key}={value}");
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::{Cli, Command, ProviderKind};
+
+    #[test]
+    fn parses_authorize_url_subcommand() {
+        let cli = Cli::try_parse_from(["ai-connect", "authorize-url", "openai"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Command::AuthorizeUrl {
+                provider: ProviderKind::Openai
+            }
+        ));
+    }
+
+    #[test]
+    fn parses_exchange_subcommand() {
+        let cli = Cli::try_parse_from([
+            "ai-connect",
+            "exchange",
+            "anthropic",
+            "--redirect-url",
+            "http://localhost:8765/callback?code=abc&state=xyz",
+            "--verifier",
+            "verifier",
+            "--state",
+            "xyz",
+        ])
+        .unwrap();
+        let Command::Exchange {
+            provider,
+            redirect_url,
+            verifier,
+            state,
+        } = cli.command
+        else {
+            panic!("expected exchange subcommand");
+        };
+        assert_eq!(provider, ProviderKind::Anthropic);
+        assert!(redirect_url.contains("code=abc"));
+        assert_eq!(verifier, "verifier");
+        assert_eq!(state.as_deref(), Some("xyz"));
+
+        assert!(Cli::try_parse_from(["ai-connect", "exchange", "openai"]).is_err());
+    }
+}
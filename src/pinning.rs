@@ -0,0 +1,223 @@
+use std::error::Error as StdError;
+use std::sync::Arc;
+
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::{CryptoProvider, aws_lc_rs, verify_tls12_signature, verify_tls13_signature};
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{CertificateError, ClientConfig, DigitallySignedStruct, SignatureScheme};
+use sha2::{Digest, Sha256};
+
+use crate::OAuthError;
+
+// The pin replaces CA validation: a leaf whose SHA-256 matches is trusted
+// outright, so self-signed endpoints can be pinned too. Handshake signatures
+// are still verified against the pinned certificate's key.
+#[derive(Debug)]
+struct PinnedCertVerifier {
+    fingerprint: [u8; 32],
+    provider: Arc<CryptoProvider>,
+}
+
+impl ServerCertVerifier for PinnedCertVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        if Sha256::digest(end_entity.as_ref()).as_slice() == self.fingerprint {
+            Ok(ServerCertVerified::assertion())
+        } else {
+            Err(rustls::Error::InvalidCertificate(
+                CertificateError::ApplicationVerificationFailure,
+            ))
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
+
+pub(crate) fn pinned_tls_config(hex: &str) -> Result<ClientConfig, OAuthError> {
+    let fingerprint = parse_fingerprint(hex).ok_or_else(|| OAuthError::InvalidParam {
+        name: "certificate pin".to_string(),
+        message: "expected 64 hex characters (a SHA-256 fingerprint)".to_string(),
+    })?;
+    let provider = Arc::new(aws_lc_rs::default_provider());
+    let verifier = PinnedCertVerifier {
+        fingerprint,
+        provider: provider.clone(),
+    };
+    let config = ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .map_err(|err| OAuthError::InvalidParam {
+            name: "certificate pin".to_string(),
+            message: err.to_string(),
+        })?
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(verifier))
+        .with_no_client_auth();
+    Ok(config)
+}
+
+// reqwest buries the rustls error inside io::Error values in its source chain.
+pub(crate) fn is_pin_mismatch(err: &reqwest::Error) -> bool {
+    let mut source: Option<&(dyn StdError + 'static)> = Some(err);
+    while let Some(err) = source {
+        let mut inner = err;
+        while let Some(wrapped) = inner
+            .downcast_ref::<std::io::Error>()
+            .and_then(|io| io.get_ref())
+        {
+            inner = wrapped;
+        }
+        if let Some(rustls::Error::InvalidCertificate(
+            CertificateError::ApplicationVerificationFailure,
+        )) = inner.downcast_ref::<rustls::Error>()
+        {
+            return true;
+        }
+        source = err.source();
+    }
+    false
+}
+
+fn parse_fingerprint(hex: &str) -> Option<[u8; 32]> {
+    let hex: String = hex.chars().filter(|c| *c != ':').collect();
+    if hex.len() != 64 || !hex.is_ascii() {
+        return None;
+    }
+    let mut fingerprint = [0u8; 32];
+    for (byte, pair) in fingerprint.iter_mut().zip(hex.as_bytes().chunks(2)) {
+        *byte = u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok()?;
+    }
+    Some(fingerprint)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::sync::Arc;
+    use std::thread;
+
+    use rustls::pki_types::pem::PemObject;
+    use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+    use rustls::{ServerConfig, ServerConnection, StreamOwned};
+    use sha2::{Digest, Sha256};
+
+    use super::parse_fingerprint;
+    use crate::test_support::TestProvider;
+    use crate::{OAuthClient, OAuthClientConfig, OAuthError};
+
+    const CERT_PEM: &[u8] = include_bytes!("../tests/fixtures/localhost.pem");
+    const KEY_PEM: &[u8] = include_bytes!("../tests/fixtures/localhost.key");
+    const TOKEN_BODY: &str = r#"{"access_token":"pinned"}"#;
+
+    fn cert_fingerprint() -> String {
+        let cert = CertificateDer::from_pem_slice(CERT_PEM).unwrap();
+        Sha256::digest(cert.as_ref())
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect()
+    }
+
+    // Serves a single TLS connection on a background thread.
+    fn start_tls_server() -> String {
+        let cert = CertificateDer::from_pem_slice(CERT_PEM).unwrap();
+        let key = PrivateKeyDer::from_pem_slice(KEY_PEM).unwrap();
+        let config = ServerConfig::builder_with_provider(Arc::new(
+            rustls::crypto::aws_lc_rs::default_provider(),
+        ))
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .with_no_client_auth()
+        .with_single_cert(vec![cert], key)
+        .unwrap();
+        let config = Arc::new(config);
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            if let Ok((stream, _)) = listener.accept() {
+                let connection = ServerConnection::new(config).unwrap();
+                let mut tls = StreamOwned::new(connection, stream);
+                let mut buf = [0u8; 4096];
+                if tls.read(&mut buf).is_ok() {
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{TOKEN_BODY}",
+                        TOKEN_BODY.len()
+                    );
+                    let _ = tls.write_all(response.as_bytes());
+                    let _ = tls.flush();
+                }
+            }
+        });
+        format!("https://{addr}/token")
+    }
+
+    fn pinned_client(token_url: String, pin: String) -> OAuthClient<TestProvider> {
+        let provider = TestProvider::new(Box::leak(token_url.into_boxed_str()));
+        let config = OAuthClientConfig::new("client-id", "http://localhost:8765/callback")
+            .pin_certificate_sha256(pin);
+        OAuthClient::new(provider, config).unwrap()
+    }
+
+    #[tokio::test]
+    async fn matching_pin_accepts_the_endpoint() {
+        let client = pinned_client(start_tls_server(), cert_fingerprint());
+        let token = client.refresh_token("refresh").await.unwrap();
+        assert_eq!(token.access_token, "pinned");
+    }
+
+    #[tokio::test]
+    async fn mismatching_pin_is_rejected() {
+        let client = pinned_client(start_tls_server(), "00".repeat(32));
+        let error = client.refresh_token("refresh").await.unwrap_err();
+        assert!(
+            matches!(error, OAuthError::CertificatePinMismatch { .. }),
+            "unexpected error: {error:?}"
+        );
+    }
+
+    #[test]
+    fn fingerprints_accept_colon_separated_hex() {
+        let colons = ["ab"; 32].join(":");
+        assert_eq!(parse_fingerprint(&colons), Some([0xab; 32]));
+        assert_eq!(parse_fingerprint("abc"), None);
+        assert_eq!(parse_fingerprint(&"zz".repeat(32)), None);
+    }
+}
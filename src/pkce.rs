@@ -1,44 +1,182 @@
+use std::sync::Arc;
+
 use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
-use rand::{TryRngCore, rngs::OsRng};
+use rand::{RngCore, TryRngCore, rngs::OsRng};
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 
 use crate::OAuthError;
 
 const VERIFIER_BYTES: usize = 32;
+const VERIFIER_CHARS: std::ops::RangeInclusive<usize> = 43..=128;
+const RNG_ATTEMPTS: usize = 3;
+
+pub type RngFallback = Arc<dyn Fn(&mut [u8]) + Send + Sync>;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PkceMethod {
+    #[default]
+    S256,
+    #[serde(rename = "plain")]
+    Plain,
+}
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PkcePair {
     pub code_verifier: String,
     pub code_challenge: String,
+    #[serde(default)]
+    pub method: PkceMethod,
+}
+
+impl PkceMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::S256 => "S256",
+            Self::Plain => "plain",
+        }
+    }
 }
 
 impl PkcePair {
     pub fn generate() -> Result<Self, OAuthError> {
+        Self::generate_from(PkceMethod::S256, None)
+    }
+
+    pub fn generate_with_method(method: PkceMethod) -> Result<Self, OAuthError> {
+        Self::generate_from(method, None)
+    }
+
+    pub fn generate_with_fallback(fallback: &RngFallback) -> Result<Self, OAuthError> {
+        Self::generate_from(PkceMethod::S256, Some(fallback))
+    }
+
+    pub fn generate_with_rng<R: RngCore>(rng: &mut R) -> Self {
         let mut bytes = [0u8; VERIFIER_BYTES];
-        OsRng
-            .try_fill_bytes(&mut bytes)
-            .map_err(|err| OAuthError::OsRng {
-                message: err.to_string(),
-            })?;
-        Ok(Self::from_verifier(URL_SAFE_NO_PAD.encode(bytes)))
+        rng.fill_bytes(&mut bytes);
+        Self::from_verifier(URL_SAFE_NO_PAD.encode(bytes))
+    }
+
+    pub fn generate_with_length(bytes: usize) -> Result<Self, OAuthError> {
+        // Saturate rather than overflow; any length that big is rejected below.
+        let length = bytes
+            .checked_mul(4)
+            .map_or(usize::MAX, |scaled| scaled.div_ceil(3));
+        if !VERIFIER_CHARS.contains(&length) {
+            return Err(OAuthError::InvalidPkceLength { length });
+        }
+        Self::generate_sized(bytes, PkceMethod::S256, None)
+    }
+
+    pub(crate) fn generate_from(
+        method: PkceMethod,
+        fallback: Option<&RngFallback>,
+    ) -> Result<Self, OAuthError> {
+        Self::generate_sized(VERIFIER_BYTES, method, fallback)
+    }
+
+    fn generate_sized(
+        len: usize,
+        method: PkceMethod,
+        fallback: Option<&RngFallback>,
+    ) -> Result<Self, OAuthError> {
+        Ok(Self::from_verifier_with_method(
+            random_string(len, fallback)?,
+            method,
+        ))
+    }
+
+    pub fn from_verifier_checked(code_verifier: impl Into<String>) -> Result<Self, OAuthError> {
+        let code_verifier = code_verifier.into();
+        validate_verifier(&code_verifier)?;
+        Ok(Self::from_verifier(code_verifier))
     }
 
     pub fn from_verifier(code_verifier: impl Into<String>) -> Self {
+        Self::from_verifier_with_method(code_verifier, PkceMethod::S256)
+    }
+
+    pub fn from_verifier_with_method(code_verifier: impl Into<String>, method: PkceMethod) -> Self {
         let code_verifier = code_verifier.into();
-        let mut hasher = Sha256::new();
-        hasher.update(code_verifier.as_bytes());
-        let digest = hasher.finalize();
-        let code_challenge = URL_SAFE_NO_PAD.encode(digest);
+        let code_challenge = match method {
+            PkceMethod::S256 => {
+                let mut hasher = Sha256::new();
+                hasher.update(code_verifier.as_bytes());
+                URL_SAFE_NO_PAD.encode(hasher.finalize())
+            }
+            PkceMethod::Plain => code_verifier.clone(),
+        };
         Self {
             code_verifier,
             code_challenge,
+            method,
+        }
+    }
+
+    pub fn method(&self) -> PkceMethod {
+        self.method
+    }
+
+    pub fn method_str(&self) -> &'static str {
+        self.method.as_str()
+    }
+}
+
+// RFC 7636 §4.1: 43-128 characters from the unreserved set.
+pub(crate) fn validate_verifier(code_verifier: &str) -> Result<(), OAuthError> {
+    if let Some(invalid) = code_verifier
+        .chars()
+        .find(|c| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '_' | '~')))
+    {
+        return Err(OAuthError::InvalidPkceChars { invalid });
+    }
+    let length = code_verifier.len();
+    if !VERIFIER_CHARS.contains(&length) {
+        return Err(OAuthError::InvalidPkceLength { length });
+    }
+    Ok(())
+}
+
+pub(crate) fn random_string(
+    len: usize,
+    fallback: Option<&RngFallback>,
+) -> Result<String, OAuthError> {
+    let mut bytes = vec![0u8; len];
+    fill_random(&mut bytes, fallback)?;
+    Ok(URL_SAFE_NO_PAD.encode(bytes))
+}
+
+pub(crate) fn fill_random(
+    bytes: &mut [u8],
+    fallback: Option<&RngFallback>,
+) -> Result<(), OAuthError> {
+    match (fill_with_retry(&mut OsRng, bytes), fallback) {
+        (Err(_), Some(fallback)) => {
+            fallback(bytes);
+            Ok(())
+        }
+        (result, _) => result,
+    }
+}
+
+fn fill_with_retry<R: TryRngCore>(rng: &mut R, bytes: &mut [u8]) -> Result<(), OAuthError> {
+    let mut message = String::new();
+    for _ in 0..RNG_ATTEMPTS {
+        match rng.try_fill_bytes(bytes) {
+            Ok(()) => return Ok(()),
+            Err(err) => message = err.to_string(),
         }
     }
+    Err(OAuthError::OsRng { message })
 }
 
 #[cfg(test)]
 mod tests {
-    use super::PkcePair;
+    use rand::rngs::StdRng;
+    use rand::{SeedableRng, TryRngCore};
+
+    use super::{PkceMethod, PkcePair, fill_with_retry};
+    use crate::OAuthError;
 
     #[test]
     fn generates_url_safe_pkce() {
@@ -49,4 +187,126 @@ mod tests {
             assert!(!value.contains('/'), "pkce values should be url safe");
         }
     }
+
+    #[test]
+    fn generate_with_length_enforces_rfc_bounds() {
+        assert_eq!(PkcePair::generate().unwrap().code_verifier.len(), 43);
+        assert_eq!(
+            PkcePair::generate_with_length(96)
+                .unwrap()
+                .code_verifier
+                .len(),
+            128
+        );
+        for bytes in [31, 97, usize::MAX / 2, usize::MAX] {
+            assert!(matches!(
+                PkcePair::generate_with_length(bytes),
+                Err(OAuthError::InvalidPkceLength { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn from_verifier_checked_accepts_url_safe_and_rejects_standard_base64() {
+        let url_safe = format!("{}-_", "a".repeat(41));
+        let pkce = PkcePair::from_verifier_checked(url_safe.clone()).unwrap();
+        assert_eq!(pkce.code_verifier, url_safe);
+
+        for invalid in ['+', '/', '='] {
+            let verifier = format!("{}{invalid}", "a".repeat(42));
+            assert!(matches!(
+                PkcePair::from_verifier_checked(verifier),
+                Err(OAuthError::InvalidPkceChars { invalid: found }) if found == invalid
+            ));
+        }
+
+        for _ in 0..32 {
+            let generated = PkcePair::generate().unwrap().code_verifier;
+            assert!(PkcePair::from_verifier_checked(generated).is_ok());
+        }
+    }
+
+    #[test]
+    fn generate_with_rng_is_deterministic_for_a_seed() {
+        let first = PkcePair::generate_with_rng(&mut StdRng::seed_from_u64(7));
+        let second = PkcePair::generate_with_rng(&mut StdRng::seed_from_u64(7));
+        assert_eq!(first.code_verifier, second.code_verifier);
+        assert_eq!(first.code_challenge, second.code_challenge);
+        assert_eq!(first.code_verifier.len(), 43);
+
+        let other = PkcePair::generate_with_rng(&mut StdRng::seed_from_u64(8));
+        assert_ne!(first.code_verifier, other.code_verifier);
+    }
+
+    struct FlakyRng {
+        failures: usize,
+    }
+
+    impl TryRngCore for FlakyRng {
+        type Error = &'static str;
+
+        fn try_next_u32(&mut self) -> Result<u32, Self::Error> {
+            Ok(7)
+        }
+
+        fn try_next_u64(&mut self) -> Result<u64, Self::Error> {
+            Ok(7)
+        }
+
+        fn try_fill_bytes(&mut self, dst: &mut [u8]) -> Result<(), Self::Error> {
+            if self.failures > 0 {
+                self.failures -= 1;
+                return Err("entropy not ready");
+            }
+            dst.fill(7);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn rng_failures_are_retried() {
+        let mut bytes = [0u8; 4];
+        fill_with_retry(&mut FlakyRng { failures: 1 }, &mut bytes).unwrap();
+        assert_eq!(bytes, [7; 4]);
+
+        let result = fill_with_retry(&mut FlakyRng { failures: 3 }, &mut bytes);
+        assert!(result.is_err_and(|err| err.to_string().contains("entropy not ready")));
+    }
+
+    #[test]
+    fn method_survives_serialization() {
+        for method in [PkceMethod::S256, PkceMethod::Plain] {
+            let pkce = PkcePair::from_verifier_with_method("verifier-value", method);
+            let json = serde_json::to_string(&pkce).unwrap();
+            let restored: PkcePair = serde_json::from_str(&json).unwrap();
+            assert_eq!(restored.method, method);
+            assert_eq!(restored.code_challenge, pkce.code_challenge);
+
+            let rebuilt =
+                PkcePair::from_verifier_with_method(restored.code_verifier, restored.method);
+            assert_eq!(rebuilt.code_challenge, pkce.code_challenge);
+        }
+    }
+
+    #[test]
+    fn plain_challenge_equals_verifier() {
+        let pkce = PkcePair::generate_with_method(PkceMethod::Plain).unwrap();
+        assert_eq!(pkce.method(), PkceMethod::Plain);
+        assert_eq!(pkce.code_challenge, pkce.code_verifier);
+        assert_eq!(PkcePair::generate().unwrap().method(), PkceMethod::S256);
+    }
+
+    #[test]
+    fn method_str_matches_wire_names() {
+        assert_eq!(PkcePair::from_verifier("verifier").method_str(), "S256");
+        let plain = PkcePair::from_verifier_with_method("verifier", PkceMethod::Plain);
+        assert_eq!(plain.method_str(), "plain");
+    }
+
+    #[test]
+    fn method_defaults_to_s256_when_missing() {
+        let restored: PkcePair =
+            serde_json::from_str(r#"{"code_verifier":"a","code_challenge":"b"}"#).unwrap();
+        assert_eq!(restored.method, PkceMethod::S256);
+    }
 }
@@ -0,0 +1,7 @@
+//! Common imports for getting started: `use ai_connect::prelude::*;`
+
+pub use crate::{
+    AnthropicProvider, AuthorizationRequest, DiscordProvider, DiscoveryProvider, GenericProvider,
+    GitHubProvider, GoogleProvider, OAuthClient, OAuthClientConfig, OAuthError, OAuthProvider,
+    OpenAIProvider, PkcePair, TokenResponse, TwitchProvider,
+};
@@ -1,4 +1,4 @@
-use crate::OAuthProvider;
+use crate::{OAuthErrorResponse, OAuthProvider};
 
 const AUTHORIZE_URL: &str = "https://claude.ai/oauth/authorize";
 const TOKEN_URL: &str = "https://console.anthropic.com/v1/oauth/token";
@@ -29,6 +29,10 @@ impl OAuthProvider for AnthropicProvider {
         DEFAULT_SCOPE
     }
 
+    fn is_known_redirect_uri(&self, uri: &str) -> bool {
+        uri == DEFAULT_REDIRECT_URI
+    }
+
     fn authorize_params(&self) -> Vec<(String, String)> {
         AUTHORIZE_PARAMS
             .iter()
@@ -39,6 +43,16 @@ impl OAuthProvider for AnthropicProvider {
     fn include_state_in_token_request(&self) -> bool {
         true
     }
+
+    fn interpret_error(&self, err: &OAuthErrorResponse) -> Option<&'static str> {
+        match err.error.as_str() {
+            "invalid_grant" => Some(
+                "the authorization code expired or was already used; start the login flow again",
+            ),
+            "invalid_client" => Some("the client id is not recognized by Anthropic"),
+            _ => None,
+        }
+    }
 }
 
 impl AnthropicProvider {
@@ -50,3 +64,25 @@ impl AnthropicProvider {
         DEFAULT_REDIRECT_URI
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::AnthropicProvider;
+    use crate::{OAuthErrorResponse, OAuthProvider};
+
+    #[test]
+    fn interpret_error_hints_known_codes() {
+        let err = OAuthErrorResponse::from_body(
+            r#"{"error":"invalid_grant","error_description":"Invalid 'code' in request."}"#,
+        )
+        .unwrap();
+        assert!(
+            AnthropicProvider
+                .interpret_error(&err)
+                .is_some_and(|hint| hint.contains("login flow again"))
+        );
+
+        let unknown = OAuthErrorResponse::from_body(r#"{"error":"temporarily_unavailable"}"#);
+        assert_eq!(AnthropicProvider.interpret_error(&unknown.unwrap()), None);
+    }
+}
@@ -0,0 +1,55 @@
+use crate::{OAuthProvider, TokenRequestFormat};
+
+const AUTHORIZE_URL: &str = "https://discord.com/oauth2/authorize";
+const TOKEN_URL: &str = "https://discord.com/api/oauth2/token";
+
+const DEFAULT_SCOPE: &str = "identify";
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DiscordProvider;
+
+impl OAuthProvider for DiscordProvider {
+    fn id(&self) -> &'static str {
+        "discord"
+    }
+
+    fn authorize_url(&self) -> &'static str {
+        AUTHORIZE_URL
+    }
+
+    fn token_url(&self) -> &'static str {
+        TOKEN_URL
+    }
+
+    fn default_scope(&self) -> &'static str {
+        DEFAULT_SCOPE
+    }
+
+    fn token_request_format(&self) -> TokenRequestFormat {
+        TokenRequestFormat::Form
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use url::Url;
+
+    use super::DiscordProvider;
+    use crate::{OAuthClient, OAuthClientConfig, OAuthProvider, TokenRequestFormat};
+
+    #[test]
+    fn authorize_url_targets_discord_with_form_exchange() {
+        let config = OAuthClientConfig::new("client-id", "http://localhost:8765/callback");
+        let client = OAuthClient::new(DiscordProvider, config).unwrap();
+        let auth = client.authorization_url().unwrap();
+
+        let url = Url::parse(&auth.authorization_url).unwrap();
+        assert_eq!(url.host_str(), Some("discord.com"));
+        assert_eq!(url.path(), "/oauth2/authorize");
+        assert_eq!(auth.scope, "identify");
+        assert_eq!(
+            DiscordProvider.token_request_format(),
+            TokenRequestFormat::Form
+        );
+    }
+}
@@ -0,0 +1,163 @@
+use serde::Deserialize;
+
+use crate::client::{DEFAULT_MAX_RESPONSE_BYTES, HttpReply};
+use crate::{OAuthError, OAuthProvider, TokenRequestFormat};
+
+const DISCOVERY_PATH: &str = "/.well-known/openid-configuration";
+const DEFAULT_SCOPE: &str = "openid";
+
+#[derive(Debug, Clone)]
+pub struct DiscoveryProvider {
+    issuer: String,
+    authorize_url: String,
+    token_url: String,
+    device_authorization_url: Option<String>,
+    scopes_supported: Vec<String>,
+    default_scope: String,
+}
+
+#[derive(Deserialize)]
+struct DiscoveryDocument {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    device_authorization_endpoint: Option<String>,
+    #[serde(default)]
+    scopes_supported: Vec<String>,
+}
+
+impl DiscoveryProvider {
+    pub async fn discover(issuer: &str, http: &reqwest::Client) -> Result<Self, OAuthError> {
+        let issuer = issuer.trim_end_matches('/');
+        let response = http.get(format!("{issuer}{DISCOVERY_PATH}")).send().await?;
+        let HttpReply { status, body, .. } =
+            HttpReply::from_response(response, DEFAULT_MAX_RESPONSE_BYTES).await?;
+        if !status.is_success() {
+            return Err(OAuthError::HttpStatus {
+                status: status.as_u16(),
+                body,
+            });
+        }
+
+        let document: DiscoveryDocument = match serde_json::from_str(&body) {
+            Ok(document) => document,
+            Err(err) => {
+                return Err(OAuthError::InvalidResponse {
+                    message: err.to_string(),
+                    body,
+                });
+            }
+        };
+        if document.issuer.trim_end_matches('/') != issuer {
+            return Err(OAuthError::InvalidResponse {
+                message: format!(
+                    "discovery document issuer {} does not match {issuer}",
+                    document.issuer
+                ),
+                body,
+            });
+        }
+
+        Ok(Self {
+            issuer: document.issuer,
+            authorize_url: document.authorization_endpoint,
+            token_url: document.token_endpoint,
+            device_authorization_url: document.device_authorization_endpoint,
+            scopes_supported: document.scopes_supported,
+            default_scope: DEFAULT_SCOPE.to_string(),
+        })
+    }
+
+    pub fn with_default_scope(mut self, scope: impl Into<String>) -> Self {
+        self.default_scope = scope.into();
+        self
+    }
+
+    pub fn issuer(&self) -> &str {
+        &self.issuer
+    }
+
+    pub fn scopes_supported(&self) -> &[String] {
+        &self.scopes_supported
+    }
+}
+
+impl OAuthProvider for DiscoveryProvider {
+    fn id(&self) -> &str {
+        &self.issuer
+    }
+
+    fn authorize_url(&self) -> &str {
+        &self.authorize_url
+    }
+
+    fn token_url(&self) -> &str {
+        &self.token_url
+    }
+
+    fn default_scope(&self) -> &str {
+        &self.default_scope
+    }
+
+    fn device_authorization_url(&self) -> Option<&str> {
+        self.device_authorization_url.as_deref()
+    }
+
+    fn token_request_format(&self) -> TokenRequestFormat {
+        TokenRequestFormat::Form
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DiscoveryProvider;
+    use crate::test_support::{MockResponse, MockServer};
+    use crate::{OAuthError, OAuthProvider};
+
+    #[tokio::test]
+    async fn discover_populates_endpoints_from_document() {
+        let server = MockServer::start_with(|issuer| {
+            vec![MockResponse::json(
+                200,
+                format!(
+                    r#"{{"issuer":"{issuer}","authorization_endpoint":"{issuer}/authorize","token_endpoint":"{issuer}/token","scopes_supported":["openid","email"]}}"#
+                ),
+            )]
+        });
+        let issuer = server.url("/");
+
+        let provider = DiscoveryProvider::discover(&issuer, &reqwest::Client::new())
+            .await
+            .unwrap();
+        assert_eq!(provider.authorize_url(), server.url("/authorize"));
+        assert_eq!(provider.token_url(), server.url("/token"));
+        assert_eq!(provider.scopes_supported(), ["openid", "email"]);
+        assert_eq!(
+            server.requests()[0].path,
+            "/.well-known/openid-configuration"
+        );
+    }
+
+    #[tokio::test]
+    async fn discover_rejects_mismatched_issuer() {
+        let server = MockServer::start(vec![MockResponse::json(
+            200,
+            r#"{"issuer":"https://other.example.com","authorization_endpoint":"https://other.example.com/authorize","token_endpoint":"https://other.example.com/token"}"#,
+        )]);
+
+        let result = DiscoveryProvider::discover(&server.url(""), &reqwest::Client::new()).await;
+        assert!(matches!(result, Err(OAuthError::InvalidResponse { .. })));
+    }
+
+    #[tokio::test]
+    async fn discover_caps_the_document_size() {
+        let oversized = format!(r#"{{"padding":"{}"}}"#, "x".repeat(2 * 1024 * 1024));
+        let server = MockServer::start(vec![MockResponse::json(200, oversized)]);
+
+        let result = DiscoveryProvider::discover(&server.url(""), &reqwest::Client::new()).await;
+        assert!(
+            matches!(&result, Err(OAuthError::InvalidResponse { message, .. }) if message.contains("exceeds")),
+            "unexpected result: {result:?}"
+        );
+    }
+}
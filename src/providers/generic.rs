@@ -0,0 +1,91 @@
+use crate::{OAuthProvider, TokenRequestFormat};
+
+#[derive(Debug, Clone)]
+pub struct GenericProvider {
+    id: String,
+    authorize_url: String,
+    token_url: String,
+    default_scope: String,
+    device_authorization_url: Option<String>,
+    revocation_url: Option<String>,
+    introspection_url: Option<String>,
+    token_request_format: TokenRequestFormat,
+}
+
+impl GenericProvider {
+    pub fn new(
+        id: impl Into<String>,
+        authorize_url: impl Into<String>,
+        token_url: impl Into<String>,
+    ) -> Self {
+        Self {
+            id: id.into(),
+            authorize_url: authorize_url.into(),
+            token_url: token_url.into(),
+            default_scope: String::new(),
+            device_authorization_url: None,
+            revocation_url: None,
+            introspection_url: None,
+            token_request_format: TokenRequestFormat::Json,
+        }
+    }
+
+    pub fn with_default_scope(mut self, scope: impl Into<String>) -> Self {
+        self.default_scope = scope.into();
+        self
+    }
+
+    pub fn with_device_authorization_url(mut self, url: impl Into<String>) -> Self {
+        self.device_authorization_url = Some(url.into());
+        self
+    }
+
+    pub fn with_revocation_url(mut self, url: impl Into<String>) -> Self {
+        self.revocation_url = Some(url.into());
+        self
+    }
+
+    pub fn with_introspection_url(mut self, url: impl Into<String>) -> Self {
+        self.introspection_url = Some(url.into());
+        self
+    }
+
+    pub fn with_token_request_format(mut self, format: TokenRequestFormat) -> Self {
+        self.token_request_format = format;
+        self
+    }
+}
+
+impl OAuthProvider for GenericProvider {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn authorize_url(&self) -> &str {
+        &self.authorize_url
+    }
+
+    fn token_url(&self) -> &str {
+        &self.token_url
+    }
+
+    fn default_scope(&self) -> &str {
+        &self.default_scope
+    }
+
+    fn device_authorization_url(&self) -> Option<&str> {
+        self.device_authorization_url.as_deref()
+    }
+
+    fn revocation_url(&self) -> Option<&str> {
+        self.revocation_url.as_deref()
+    }
+
+    fn introspection_url(&self) -> Option<&str> {
+        self.introspection_url.as_deref()
+    }
+
+    fn token_request_format(&self) -> TokenRequestFormat {
+        self.token_request_format
+    }
+}
@@ -0,0 +1,65 @@
+use crate::{OAuthProvider, TokenRequestFormat};
+
+const AUTHORIZE_URL: &str = "https://github.com/login/oauth/authorize";
+const TOKEN_URL: &str = "https://github.com/login/oauth/access_token";
+
+#[derive(Debug, Clone, Default)]
+pub struct GitHubProvider {
+    scope: String,
+}
+
+impl OAuthProvider for GitHubProvider {
+    fn id(&self) -> &'static str {
+        "github"
+    }
+
+    fn authorize_url(&self) -> &'static str {
+        AUTHORIZE_URL
+    }
+
+    fn token_url(&self) -> &'static str {
+        TOKEN_URL
+    }
+
+    fn default_scope(&self) -> &str {
+        &self.scope
+    }
+
+    fn token_request_format(&self) -> TokenRequestFormat {
+        TokenRequestFormat::Form
+    }
+
+    // GitHub answers with a form-encoded body unless JSON is requested explicitly.
+    fn token_headers(&self) -> Vec<(String, String)> {
+        vec![("Accept".to_string(), "application/json".to_string())]
+    }
+}
+
+impl GitHubProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_scopes(mut self, scopes: &[&str]) -> Self {
+        self.scope = scopes.join(" ");
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GitHubProvider;
+    use crate::OAuthProvider;
+
+    #[test]
+    fn scopes_default_empty_and_join_with_spaces() {
+        assert_eq!(GitHubProvider::new().default_scope(), "");
+
+        let provider = GitHubProvider::new().with_scopes(&["repo", "read:user"]);
+        assert_eq!(provider.default_scope(), "repo read:user");
+        assert_eq!(
+            provider.token_headers(),
+            [("Accept".to_string(), "application/json".to_string())]
+        );
+    }
+}
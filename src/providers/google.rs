@@ -0,0 +1,129 @@
+use crate::{OAuthClientConfig, OAuthError, OAuthProvider, TokenRequestFormat};
+
+const AUTHORIZE_URL: &str = "https://accounts.google.com/o/oauth2/v2/auth";
+const TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+const REVOCATION_URL: &str = "https://oauth2.googleapis.com/revoke";
+
+const DEFAULT_REDIRECT_URI: &str = "http://127.0.0.1:8765/callback";
+const DEFAULT_SCOPE: &str = "openid email profile";
+
+// Google only issues a refresh token on offline access with an explicit consent prompt.
+const AUTHORIZE_PARAMS: &[(&str, &str)] = &[("access_type", "offline"), ("prompt", "consent")];
+
+#[derive(Debug, Clone, Default)]
+pub struct GoogleProvider {
+    client_id: Option<String>,
+}
+
+impl OAuthProvider for GoogleProvider {
+    fn id(&self) -> &'static str {
+        "google"
+    }
+
+    fn authorize_url(&self) -> &'static str {
+        AUTHORIZE_URL
+    }
+
+    fn token_url(&self) -> &'static str {
+        TOKEN_URL
+    }
+
+    fn default_scope(&self) -> &'static str {
+        DEFAULT_SCOPE
+    }
+
+    fn revocation_url(&self) -> Option<&'static str> {
+        Some(REVOCATION_URL)
+    }
+
+    fn authorize_params(&self) -> Vec<(String, String)> {
+        AUTHORIZE_PARAMS
+            .iter()
+            .map(|(key, value)| ((*key).to_string(), (*value).to_string()))
+            .collect()
+    }
+
+    fn token_request_format(&self) -> TokenRequestFormat {
+        TokenRequestFormat::Form
+    }
+}
+
+impl GoogleProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_client_id(mut self, client_id: impl Into<String>) -> Self {
+        self.client_id = Some(client_id.into());
+        self
+    }
+
+    pub fn client_id(&self) -> Option<&str> {
+        self.client_id.as_deref()
+    }
+
+    // Google has no public client for installed apps, so unlike Anthropic and
+    // OpenAI there is no built-in id; register one and pass it to
+    // `with_client_id`.
+    pub fn default_client_id() -> Option<&'static str> {
+        None
+    }
+
+    // Config for the id set with `with_client_id` and the default redirect URI.
+    pub fn client_config(&self) -> Result<OAuthClientConfig, OAuthError> {
+        let client_id = self
+            .client_id
+            .as_deref()
+            .ok_or_else(|| OAuthError::InvalidParam {
+                name: "client_id".to_string(),
+                message: "GoogleProvider needs a client id from with_client_id".to_string(),
+            })?;
+        Ok(OAuthClientConfig::new(client_id, DEFAULT_REDIRECT_URI))
+    }
+
+    pub fn default_redirect_uri() -> &'static str {
+        DEFAULT_REDIRECT_URI
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use url::Url;
+
+    use super::GoogleProvider;
+    use crate::{OAuthClient, OAuthError, OAuthProvider, TokenRequestFormat};
+
+    #[test]
+    fn authorize_url_requests_offline_access() {
+        let provider = GoogleProvider::new().with_client_id("google-client");
+        let config = provider.client_config().unwrap();
+        assert_eq!(config.client_id, "google-client");
+        assert_eq!(config.redirect_uri, GoogleProvider::default_redirect_uri());
+        assert_eq!(provider.token_request_format(), TokenRequestFormat::Form);
+        let client = OAuthClient::new(provider, config).unwrap();
+
+        let auth = client.authorization_url().unwrap();
+        let url = Url::parse(&auth.authorization_url).unwrap();
+        let pairs: HashMap<_, _> = url.query_pairs().into_owned().collect();
+        assert_eq!(
+            pairs.get("access_type").map(String::as_str),
+            Some("offline")
+        );
+        assert_eq!(pairs.get("prompt").map(String::as_str), Some("consent"));
+        assert_eq!(
+            pairs.get("scope").map(String::as_str),
+            Some("openid email profile")
+        );
+    }
+
+    #[test]
+    fn client_config_requires_a_client_id() {
+        assert_eq!(GoogleProvider::default_client_id(), None);
+        let result = GoogleProvider::new().client_config();
+        assert!(
+            matches!(result, Err(OAuthError::InvalidParam { name, .. }) if name == "client_id")
+        );
+    }
+}
@@ -1,7 +1,21 @@
 mod anthropic;
+mod discord;
+mod discovery;
+mod generic;
+mod github;
+mod google;
 mod openai;
 mod provider;
+mod twitch;
 
 pub use anthropic::AnthropicProvider;
+pub use discord::DiscordProvider;
+pub use discovery::DiscoveryProvider;
+pub use generic::GenericProvider;
+pub use github::GitHubProvider;
+pub use google::GoogleProvider;
 pub use openai::OpenAIProvider;
+#[cfg(feature = "async-provider")]
+pub use provider::DynamicParams;
 pub use provider::{OAuthProvider, TokenRequestFormat};
+pub use twitch::TwitchProvider;
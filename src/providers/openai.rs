@@ -1,4 +1,4 @@
-use crate::{OAuthProvider, TokenRequestFormat};
+use crate::{OAuthErrorResponse, OAuthProvider, TokenRequestFormat};
 
 // References:
 // - https://github.com/openai/codex/blob/810ebe0d2b23cdf29f65e6ca50ee46fa1c24a877/codex-rs/login/src/server.rs#L380-L418
@@ -49,6 +49,10 @@ impl OAuthProvider for OpenAIProvider {
         DEFAULT_SCOPE
     }
 
+    fn is_known_redirect_uri(&self, uri: &str) -> bool {
+        uri == DEFAULT_REDIRECT_URI
+    }
+
     fn authorize_params(&self) -> Vec<(String, String)> {
         let mut params: Vec<(String, String)> = AUTHORIZE_PARAMS
             .iter()
@@ -67,6 +71,16 @@ impl OAuthProvider for OpenAIProvider {
     fn token_headers(&self) -> Vec<(String, String)> {
         vec![("Accept".to_string(), "application/json".to_string())]
     }
+
+    fn interpret_error(&self, err: &OAuthErrorResponse) -> Option<&'static str> {
+        match err.error.as_str() {
+            "invalid_grant" => {
+                Some("the authorization code or refresh token is no longer valid; sign in again")
+            }
+            "token_expired" => Some("the refresh token expired; sign in again"),
+            _ => None,
+        }
+    }
 }
 
 impl OpenAIProvider {
@@ -1,23 +1,109 @@
+use std::collections::HashMap;
+use std::fmt;
+#[cfg(feature = "async-provider")]
+use std::future::Future;
+#[cfg(feature = "async-provider")]
+use std::pin::Pin;
+use std::time::Duration;
+
+use url::Url;
+
+use crate::{OAuthError, OAuthErrorResponse};
+
+#[cfg(feature = "async-provider")]
+pub type DynamicParams<'a> =
+    Pin<Box<dyn Future<Output = Result<Vec<(String, String)>, OAuthError>> + Send + 'a>>;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TokenRequestFormat {
     Json,
     Form,
 }
 
+impl TokenRequestFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Form => "form",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            Self::Form => "application/x-www-form-urlencoded",
+        }
+    }
+}
+
+impl fmt::Display for TokenRequestFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 pub trait OAuthProvider: Send + Sync {
-    fn id(&self) -> &'static str;
-    fn authorize_url(&self) -> &'static str;
-    fn token_url(&self) -> &'static str;
-    fn default_scope(&self) -> &'static str;
+    fn id(&self) -> &str;
+    fn authorize_url(&self) -> &str;
+    fn token_url(&self) -> &str;
+    fn default_scope(&self) -> &str;
+
+    fn device_authorization_url(&self) -> Option<&str> {
+        None
+    }
+
+    fn is_known_redirect_uri(&self, _uri: &str) -> bool {
+        true
+    }
+
+    fn revocation_url(&self) -> Option<&str> {
+        None
+    }
+
+    fn introspection_url(&self) -> Option<&str> {
+        None
+    }
+
+    fn default_timeout(&self) -> Option<Duration> {
+        None
+    }
+
+    // Pages the local server shows when its config keeps the built-in HTML.
+    fn success_html(&self) -> Option<&str> {
+        None
+    }
+
+    fn error_html(&self) -> Option<&str> {
+        None
+    }
 
     fn authorize_params(&self) -> Vec<(String, String)> {
         Vec::new()
     }
 
+    // Awaited by `authorization_url_async` and merged over `authorize_params`,
+    // e.g. for values fetched from a secrets manager.
+    #[cfg(feature = "async-provider")]
+    fn dynamic_authorize_params(&self) -> DynamicParams<'_> {
+        Box::pin(async { Ok(Vec::new()) })
+    }
+
     fn token_params(&self) -> Vec<(String, String)> {
         Vec::new()
     }
 
+    fn refresh_params(&self) -> Vec<(String, String)> {
+        Vec::new()
+    }
+
+    fn authorization_code_grant_type(&self) -> &str {
+        "authorization_code"
+    }
+
+    fn refresh_grant_type(&self) -> &str {
+        "refresh_token"
+    }
+
     fn token_request_format(&self) -> TokenRequestFormat {
         TokenRequestFormat::Json
     }
@@ -26,7 +112,58 @@ pub trait OAuthProvider: Send + Sync {
         Vec::new()
     }
 
+    fn token_headers_for(&self, _payload: &HashMap<String, String>) -> Vec<(String, String)> {
+        self.token_headers()
+    }
+
+    fn token_field_aliases(&self) -> &[(&str, &str)] {
+        &[]
+    }
+
     fn include_state_in_token_request(&self) -> bool {
         false
     }
+
+    fn include_scope_in_token_request(&self) -> bool {
+        false
+    }
+
+    fn emit_challenge_method(&self) -> bool {
+        true
+    }
+
+    fn interpret_error(&self, _err: &OAuthErrorResponse) -> Option<&'static str> {
+        None
+    }
+
+    fn validate_endpoints(&self) -> Result<(), OAuthError> {
+        let optional = [
+            self.device_authorization_url(),
+            self.revocation_url(),
+            self.introspection_url(),
+        ];
+        let endpoints = [self.authorize_url(), self.token_url()]
+            .into_iter()
+            .chain(optional.into_iter().flatten());
+        for endpoint in endpoints {
+            if Url::parse(endpoint)?.scheme() != "https" {
+                return Err(OAuthError::InsecureEndpoint(endpoint.to_string()));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TokenRequestFormat;
+
+    #[test]
+    fn formats_map_to_content_types() {
+        assert_eq!(TokenRequestFormat::Json.content_type(), "application/json");
+        assert_eq!(
+            TokenRequestFormat::Form.content_type(),
+            "application/x-www-form-urlencoded"
+        );
+    }
 }
@@ -0,0 +1,65 @@
+use crate::{OAuthProvider, TokenRequestFormat};
+
+const AUTHORIZE_URL: &str = "https://id.twitch.tv/oauth2/authorize";
+const TOKEN_URL: &str = "https://id.twitch.tv/oauth2/token";
+
+const DEFAULT_SCOPE: &str = "user:read:email";
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TwitchProvider;
+
+impl OAuthProvider for TwitchProvider {
+    fn id(&self) -> &'static str {
+        "twitch"
+    }
+
+    fn authorize_url(&self) -> &'static str {
+        AUTHORIZE_URL
+    }
+
+    fn token_url(&self) -> &'static str {
+        TOKEN_URL
+    }
+
+    fn default_scope(&self) -> &'static str {
+        DEFAULT_SCOPE
+    }
+
+    fn token_request_format(&self) -> TokenRequestFormat {
+        TokenRequestFormat::Form
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use url::Url;
+
+    use super::TwitchProvider;
+    use crate::{AuthorizationResponse, OAuthClient, OAuthClientConfig};
+
+    #[test]
+    fn redirect_uri_passes_through_unchanged() {
+        let redirect_uri = "http://localhost:3000";
+        let config = OAuthClientConfig::new("client-id", redirect_uri);
+        let client = OAuthClient::new(TwitchProvider, config).unwrap();
+
+        let auth = client.authorization_url().unwrap();
+        let url = Url::parse(&auth.authorization_url).unwrap();
+        let pairs: HashMap<_, _> = url.query_pairs().into_owned().collect();
+        assert_eq!(
+            pairs.get("redirect_uri").map(String::as_str),
+            Some(redirect_uri)
+        );
+
+        let response = AuthorizationResponse::from_callback("code", None);
+        let payload = client
+            .exchange_payload(response, &auth.pkce.code_verifier, None)
+            .unwrap();
+        assert_eq!(
+            payload.get("redirect_uri").map(String::as_str),
+            Some(redirect_uri)
+        );
+    }
+}
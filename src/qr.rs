@@ -0,0 +1,579 @@
+// Minimal QR encoder: byte mode, error correction level L, versions 1-40.
+// Follows ISO/IEC 18004 closely enough for terminal rendering of short URLs.
+
+const MAX_VERSION: usize = 40;
+const QUIET_ZONE: usize = 4;
+
+const ECC_CODEWORDS_PER_BLOCK: [usize; MAX_VERSION + 1] = [
+    0, 7, 10, 15, 20, 26, 18, 20, 24, 30, 18, 20, 24, 26, 30, 22, 24, 28, 30, 28, 28, 28, 28, 30,
+    30, 26, 28, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30,
+];
+
+const NUM_ERROR_CORRECTION_BLOCKS: [usize; MAX_VERSION + 1] = [
+    0, 1, 1, 1, 1, 1, 2, 2, 2, 2, 4, 4, 4, 4, 4, 6, 6, 6, 6, 7, 8, 8, 9, 9, 10, 12, 12, 12, 13, 14,
+    15, 16, 17, 18, 19, 19, 20, 21, 22, 24, 25,
+];
+
+pub(crate) fn render(text: &str) -> Option<String> {
+    let code = QrCode::encode(text.as_bytes())?;
+    let size = code.size + QUIET_ZONE * 2;
+    let dark = |x: usize, y: usize| {
+        x >= QUIET_ZONE
+            && y >= QUIET_ZONE
+            && x < size - QUIET_ZONE
+            && y < size - QUIET_ZONE
+            && code.module(x - QUIET_ZONE, y - QUIET_ZONE)
+    };
+
+    let mut output = String::new();
+    for y in (0..size).step_by(2) {
+        for x in 0..size {
+            let cell = match (dark(x, y), y + 1 < size && dark(x, y + 1)) {
+                (true, true) => '█',
+                (true, false) => '▀',
+                (false, true) => '▄',
+                (false, false) => ' ',
+            };
+            output.push(cell);
+        }
+        output.push('\n');
+    }
+    Some(output)
+}
+
+struct QrCode {
+    version: usize,
+    size: usize,
+    modules: Vec<bool>,
+    is_function: Vec<bool>,
+}
+
+impl QrCode {
+    fn encode(data: &[u8]) -> Option<Self> {
+        let version = (1..=MAX_VERSION).find(|&version| {
+            4 + char_count_bits(version) + data.len() * 8 <= num_data_codewords(version) * 8
+        })?;
+
+        let mut bits = BitBuffer::default();
+        bits.append(0b0100, 4);
+        bits.append(data.len() as u32, char_count_bits(version));
+        for &byte in data {
+            bits.append(byte.into(), 8);
+        }
+
+        let capacity = num_data_codewords(version) * 8;
+        bits.append(0, (capacity - bits.len()).min(4));
+        bits.append(0, (8 - bits.len() % 8) % 8);
+        for pad in [0xEC, 0x11].into_iter().cycle() {
+            if bits.len() >= capacity {
+                break;
+            }
+            bits.append(pad, 8);
+        }
+
+        let mut code = Self {
+            version,
+            size: version * 4 + 17,
+            modules: vec![false; (version * 4 + 17).pow(2)],
+            is_function: vec![false; (version * 4 + 17).pow(2)],
+        };
+        code.draw_function_patterns();
+        code.draw_codewords(&add_ecc_and_interleave(version, &bits.to_bytes()));
+
+        let mask = (0..8)
+            .min_by_key(|&mask| {
+                code.apply_mask(mask);
+                code.draw_format_bits(mask);
+                let penalty = code.penalty_score();
+                code.apply_mask(mask);
+                penalty
+            })
+            .unwrap_or(0);
+        code.apply_mask(mask);
+        code.draw_format_bits(mask);
+        Some(code)
+    }
+
+    fn module(&self, x: usize, y: usize) -> bool {
+        self.modules[y * self.size + x]
+    }
+
+    fn set_function(&mut self, x: usize, y: usize, dark: bool) {
+        let index = y * self.size + x;
+        self.modules[index] = dark;
+        self.is_function[index] = true;
+    }
+
+    fn draw_function_patterns(&mut self) {
+        for i in 0..self.size {
+            self.set_function(6, i, i % 2 == 0);
+            self.set_function(i, 6, i % 2 == 0);
+        }
+
+        let far = self.size - 4;
+        for (x, y) in [(3, 3), (far, 3), (3, far)] {
+            self.draw_finder(x, y);
+        }
+
+        let positions = alignment_positions(self.version);
+        let last = positions.len().saturating_sub(1);
+        let corner = |index: usize| index == 0 || index == last;
+        for (i, &x) in positions.iter().enumerate() {
+            for (j, &y) in positions.iter().enumerate() {
+                let overlaps_finder = (i == 0 || j == 0) && corner(i) && corner(j);
+                if !overlaps_finder {
+                    self.draw_alignment(x, y);
+                }
+            }
+        }
+
+        self.draw_format_bits(0);
+        self.draw_version();
+    }
+
+    fn draw_finder(&mut self, x: usize, y: usize) {
+        for dy in -4i32..=4 {
+            for dx in -4i32..=4 {
+                let (xx, yy) = (x as i32 + dx, y as i32 + dy);
+                if (0..self.size as i32).contains(&xx) && (0..self.size as i32).contains(&yy) {
+                    let distance = dx.abs().max(dy.abs());
+                    self.set_function(xx as usize, yy as usize, distance != 2 && distance != 4);
+                }
+            }
+        }
+    }
+
+    fn draw_alignment(&mut self, x: usize, y: usize) {
+        for dy in -2i32..=2 {
+            for dx in -2i32..=2 {
+                let (xx, yy) = ((x as i32 + dx) as usize, (y as i32 + dy) as usize);
+                self.set_function(xx, yy, dx.abs().max(dy.abs()) != 1);
+            }
+        }
+    }
+
+    fn draw_format_bits(&mut self, mask: u32) {
+        // Error correction level L is encoded as 0b01.
+        let data = (0b01 << 3) | mask;
+        let mut remainder = data;
+        for _ in 0..10 {
+            remainder = (remainder << 1) ^ ((remainder >> 9) * 0x537);
+        }
+        let bits = ((data << 10) | remainder) ^ 0x5412;
+        let bit = |i: usize| (bits >> i) & 1 != 0;
+
+        for i in 0..=5 {
+            self.set_function(8, i, bit(i));
+        }
+        self.set_function(8, 7, bit(6));
+        self.set_function(8, 8, bit(7));
+        self.set_function(7, 8, bit(8));
+        for i in 9..15 {
+            self.set_function(14 - i, 8, bit(i));
+        }
+
+        let size = self.size;
+        for i in 0..8 {
+            self.set_function(size - 1 - i, 8, bit(i));
+        }
+        for i in 8..15 {
+            self.set_function(8, size - 15 + i, bit(i));
+        }
+        self.set_function(8, size - 8, true);
+    }
+
+    fn draw_version(&mut self) {
+        if self.version < 7 {
+            return;
+        }
+        let mut remainder = self.version as u32;
+        for _ in 0..12 {
+            remainder = (remainder << 1) ^ ((remainder >> 11) * 0x1F25);
+        }
+        let bits = ((self.version as u32) << 12) | remainder;
+        for i in 0..18 {
+            let dark = (bits >> i) & 1 != 0;
+            let (a, b) = (self.size - 11 + i % 3, i / 3);
+            self.set_function(a, b, dark);
+            self.set_function(b, a, dark);
+        }
+    }
+
+    fn draw_codewords(&mut self, data: &[u8]) {
+        let mut index = 0;
+        let mut right = self.size as i32 - 1;
+        while right >= 1 {
+            if right == 6 {
+                right = 5;
+            }
+            let upward = (right + 1) & 2 == 0;
+            for vertical in 0..self.size {
+                for offset in 0..2 {
+                    let x = (right - offset) as usize;
+                    let y = if upward {
+                        self.size - 1 - vertical
+                    } else {
+                        vertical
+                    };
+                    let cell = y * self.size + x;
+                    if !self.is_function[cell] && index < data.len() * 8 {
+                        self.modules[cell] = (data[index >> 3] >> (7 - (index & 7))) & 1 != 0;
+                        index += 1;
+                    }
+                }
+            }
+            right -= 2;
+        }
+    }
+
+    fn apply_mask(&mut self, mask: u32) {
+        for y in 0..self.size {
+            for x in 0..self.size {
+                let invert = match mask {
+                    0 => (x + y) % 2 == 0,
+                    1 => y % 2 == 0,
+                    2 => x % 3 == 0,
+                    3 => (x + y) % 3 == 0,
+                    4 => (x / 3 + y / 2) % 2 == 0,
+                    5 => x * y % 2 + x * y % 3 == 0,
+                    6 => (x * y % 2 + x * y % 3) % 2 == 0,
+                    _ => ((x + y) % 2 + x * y % 3) % 2 == 0,
+                };
+                let cell = y * self.size + x;
+                if invert && !self.is_function[cell] {
+                    self.modules[cell] = !self.modules[cell];
+                }
+            }
+        }
+    }
+
+    fn penalty_score(&self) -> usize {
+        let size = self.size;
+        let mut penalty = 0;
+
+        for horizontal in [true, false] {
+            for a in 0..size {
+                let line: Vec<bool> = (0..size)
+                    .map(|b| {
+                        if horizontal {
+                            self.module(b, a)
+                        } else {
+                            self.module(a, b)
+                        }
+                    })
+                    .collect();
+                penalty += run_penalty(&line) + finder_penalty(&line);
+            }
+        }
+
+        for y in 0..size - 1 {
+            for x in 0..size - 1 {
+                let color = self.module(x, y);
+                if color == self.module(x + 1, y)
+                    && color == self.module(x, y + 1)
+                    && color == self.module(x + 1, y + 1)
+                {
+                    penalty += 3;
+                }
+            }
+        }
+
+        let total = size * size;
+        let dark = self.modules.iter().filter(|&&dark| dark).count();
+        let k = (dark * 20)
+            .abs_diff(total * 10)
+            .div_ceil(total)
+            .saturating_sub(1);
+        penalty + k * 10
+    }
+}
+
+fn run_penalty(line: &[bool]) -> usize {
+    let mut penalty = 0;
+    let mut run = 1;
+    for pair in line.windows(2) {
+        if pair[0] == pair[1] {
+            run += 1;
+            if run == 5 {
+                penalty += 3;
+            } else if run > 5 {
+                penalty += 1;
+            }
+        } else {
+            run = 1;
+        }
+    }
+    penalty
+}
+
+fn finder_penalty(line: &[bool]) -> usize {
+    const PATTERN: [bool; 11] = [
+        true, false, true, true, true, false, true, false, false, false, false,
+    ];
+    line.windows(PATTERN.len())
+        .filter(|window| window.iter().eq(PATTERN.iter()) || window.iter().eq(PATTERN.iter().rev()))
+        .count()
+        * 40
+}
+
+fn char_count_bits(version: usize) -> usize {
+    if version < 10 { 8 } else { 16 }
+}
+
+fn num_raw_data_modules(version: usize) -> usize {
+    let mut result = (16 * version + 128) * version + 64;
+    if version >= 2 {
+        let alignments = version / 7 + 2;
+        result -= (25 * alignments - 10) * alignments - 55;
+        if version >= 7 {
+            result -= 36;
+        }
+    }
+    result
+}
+
+fn num_data_codewords(version: usize) -> usize {
+    num_raw_data_modules(version) / 8
+        - ECC_CODEWORDS_PER_BLOCK[version] * NUM_ERROR_CORRECTION_BLOCKS[version]
+}
+
+fn alignment_positions(version: usize) -> Vec<usize> {
+    if version == 1 {
+        return Vec::new();
+    }
+    let count = version / 7 + 2;
+    let step = (version * 8 + count * 3 + 5) / (count * 4 - 4) * 2;
+    let size = version * 4 + 17;
+    let mut positions: Vec<usize> = (0..count - 1).map(|i| size - 7 - i * step).collect();
+    positions.push(6);
+    positions.reverse();
+    positions
+}
+
+fn add_ecc_and_interleave(version: usize, data: &[u8]) -> Vec<u8> {
+    let block_count = NUM_ERROR_CORRECTION_BLOCKS[version];
+    let ecc_len = ECC_CODEWORDS_PER_BLOCK[version];
+    let raw_codewords = num_raw_data_modules(version) / 8;
+    let short_blocks = block_count - raw_codewords % block_count;
+    let short_len = raw_codewords / block_count;
+    let divisor = reed_solomon_divisor(ecc_len);
+
+    let mut blocks = Vec::with_capacity(block_count);
+    let mut offset = 0;
+    for i in 0..block_count {
+        let data_len = short_len - ecc_len + usize::from(i >= short_blocks);
+        let mut block = data[offset..offset + data_len].to_vec();
+        offset += data_len;
+        let ecc = reed_solomon_remainder(&block, &divisor);
+        if i < short_blocks {
+            block.push(0);
+        }
+        block.extend(ecc);
+        blocks.push(block);
+    }
+
+    let mut result = Vec::with_capacity(raw_codewords);
+    for i in 0..blocks[0].len() {
+        for (j, block) in blocks.iter().enumerate() {
+            if i != short_len - ecc_len || j >= short_blocks {
+                result.push(block[i]);
+            }
+        }
+    }
+    result
+}
+
+fn reed_solomon_divisor(degree: usize) -> Vec<u8> {
+    let mut result = vec![0u8; degree];
+    result[degree - 1] = 1;
+    let mut root = 1u8;
+    for _ in 0..degree {
+        for j in 0..degree {
+            result[j] = gf_multiply(result[j], root);
+            if j + 1 < degree {
+                result[j] ^= result[j + 1];
+            }
+        }
+        root = gf_multiply(root, 0x02);
+    }
+    result
+}
+
+fn reed_solomon_remainder(data: &[u8], divisor: &[u8]) -> Vec<u8> {
+    let mut result = vec![0u8; divisor.len()];
+    for &byte in data {
+        let factor = byte ^ result.remove(0);
+        result.push(0);
+        for (value, &coefficient) in result.iter_mut().zip(divisor) {
+            *value ^= gf_multiply(coefficient, factor);
+        }
+    }
+    result
+}
+
+fn gf_multiply(x: u8, y: u8) -> u8 {
+    let mut z: u32 = 0;
+    for i in (0..8).rev() {
+        z = (z << 1) ^ ((z >> 7) * 0x11D);
+        z ^= ((u32::from(y) >> i) & 1) * u32::from(x);
+    }
+    z as u8
+}
+
+#[derive(Default)]
+struct BitBuffer {
+    bits: Vec<bool>,
+}
+
+impl BitBuffer {
+    fn len(&self) -> usize {
+        self.bits.len()
+    }
+
+    fn append(&mut self, value: u32, count: usize) {
+        for i in (0..count).rev() {
+            self.bits.push((value >> i) & 1 != 0);
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        self.bits
+            .chunks(8)
+            .map(|chunk| {
+                chunk
+                    .iter()
+                    .fold(0u8, |byte, &bit| (byte << 1) | u8::from(bit))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{QUIET_ZONE, QrCode, reed_solomon_divisor, reed_solomon_remainder, render};
+
+    #[test]
+    fn reed_solomon_matches_known_ecc() {
+        // "01234567" encoded at version 1-M, from the ISO/IEC 18004 worked example.
+        let data = [
+            0x10, 0x20, 0x0C, 0x56, 0x61, 0x80, 0xEC, 0x11, 0xEC, 0x11, 0xEC, 0x11, 0xEC, 0x11,
+            0xEC, 0x11,
+        ];
+        let ecc = reed_solomon_remainder(&data, &reed_solomon_divisor(10));
+        assert_eq!(
+            ecc,
+            [0xA5, 0x24, 0xD4, 0xC1, 0xED, 0x36, 0xC7, 0x87, 0x2C, 0x55]
+        );
+    }
+
+    #[test]
+    fn picks_smallest_version_that_fits() {
+        assert_eq!(QrCode::encode(&[b'a'; 17]).unwrap().version, 1);
+        assert_eq!(QrCode::encode(&[b'a'; 18]).unwrap().version, 2);
+        assert!(QrCode::encode(&[b'a'; 3000]).is_none());
+    }
+
+    #[test]
+    fn encodes_a_known_version_1_symbol() {
+        // Reference symbol for "WBJX-QPTZ" at 1-L, produced by a separate encoder
+        // written from ISO/IEC 18004. Mask 3 has the lowest penalty whether or not
+        // the area outside the symbol counts as light for the finder-like rule.
+        const EXPECTED: [&str; 21] = [
+            "#######.#.###.#######",
+            "#.....#...##..#.....#",
+            "#.###.#.##.#..#.###.#",
+            "#.###.#.##..#.#.###.#",
+            "#.###.#.#..#..#.###.#",
+            "#.....#..####.#.....#",
+            "#######.#.#.#.#######",
+            "...........##........",
+            "####..#.######..###.#",
+            "..#.#....#####....#..",
+            "#...#.#..#.#..##.####",
+            ".###....####....##..#",
+            "#.##..###...##.#..##.",
+            "........##.#...#.####",
+            "#######....######.#..",
+            "#.....#..##......###.",
+            "#.###.#...#.#.#......",
+            "#.###.#.##.#..###.##.",
+            "#.###.#.#...#.#...#..",
+            "#.....#.##...#.#.#..#",
+            "#######.#....#.#..#..",
+        ];
+        let code = QrCode::encode(b"WBJX-QPTZ").unwrap();
+        assert_eq!(code.version, 1);
+        let rows: Vec<String> = (0..code.size)
+            .map(|y| {
+                (0..code.size)
+                    .map(|x| if code.module(x, y) { '#' } else { '.' })
+                    .collect()
+            })
+            .collect();
+        assert_eq!(rows, EXPECTED);
+
+        // Both copies of the format information, most significant bit first, must
+        // spell level L with mask 3 (ISO/IEC 18004 Table C.1).
+        let read = |cells: &[(usize, usize)]| -> String {
+            cells
+                .iter()
+                .map(|&(x, y)| if code.module(x, y) { '1' } else { '0' })
+                .collect()
+        };
+        let top_left = [
+            (0, 8),
+            (1, 8),
+            (2, 8),
+            (3, 8),
+            (4, 8),
+            (5, 8),
+            (7, 8),
+            (8, 8),
+            (8, 7),
+            (8, 5),
+            (8, 4),
+            (8, 3),
+            (8, 2),
+            (8, 1),
+            (8, 0),
+        ];
+        let split = [
+            (8, 20),
+            (8, 19),
+            (8, 18),
+            (8, 17),
+            (8, 16),
+            (8, 15),
+            (8, 14),
+            (13, 8),
+            (14, 8),
+            (15, 8),
+            (16, 8),
+            (17, 8),
+            (18, 8),
+            (19, 8),
+            (20, 8),
+        ];
+        assert_eq!(read(&top_left), "111100010011101");
+        assert_eq!(read(&split), "111100010011101");
+    }
+
+    #[test]
+    fn render_keeps_a_four_module_quiet_zone() {
+        let output = render("WBJX-QPTZ").unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        let blank = " ".repeat(21 + QUIET_ZONE * 2);
+        // Each line draws two module rows, so four light rows are two blank lines.
+        assert_eq!(lines[..2], [blank.as_str(), blank.as_str()]);
+        assert_eq!(lines[lines.len() - 2..], [blank.as_str(), blank.as_str()]);
+        for line in &lines[2..lines.len() - 2] {
+            let cells: Vec<char> = line.chars().collect();
+            assert!(cells[..QUIET_ZONE].iter().all(|&cell| cell == ' '));
+            assert!(
+                cells[cells.len() - QUIET_ZONE..]
+                    .iter()
+                    .all(|&cell| cell == ' ')
+            );
+        }
+    }
+}
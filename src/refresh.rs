@@ -0,0 +1,484 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime};
+
+use tokio::sync::{Mutex, watch};
+use tokio::task::JoinHandle;
+
+use crate::{OAuthClient, OAuthError, OAuthProvider, TokenResponse};
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+// RFC 6749 §5.2 token endpoint errors. Retrying the same refresh token cannot
+// fix any of them, so the auto-refresh task stops on these.
+const PERMANENT_REFRESH_ERRORS: &[&str] = &[
+    "invalid_request",
+    "invalid_client",
+    "invalid_grant",
+    "unauthorized_client",
+    "unsupported_grant_type",
+    "invalid_scope",
+];
+
+pub type RefreshErrorObserver = Arc<dyn Fn(&OAuthError) + Send + Sync>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RefreshPolicy {
+    pub refresh_skew: Duration,
+    pub max_backoff: Duration,
+    pub on_error: ErrorAction,
+}
+
+impl Default for RefreshPolicy {
+    fn default() -> Self {
+        Self {
+            refresh_skew: Duration::from_secs(60),
+            max_backoff: Duration::from_secs(60),
+            on_error: ErrorAction::Retry,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ErrorAction {
+    #[default]
+    Retry,
+    StopAfter(u32),
+}
+
+// Shared by every clone of an `OAuthClient`. `completed` counts successful
+// refreshes, so a caller can tell whether the stored result finished after it
+// started waiting.
+pub(crate) type RefreshFlight = Arc<SharedRefresh>;
+
+#[derive(Debug, Default)]
+pub(crate) struct SharedRefresh {
+    completed: AtomicU64,
+    last: Mutex<Option<CompletedRefresh>>,
+}
+
+#[derive(Debug)]
+struct CompletedRefresh {
+    generation: u64,
+    refresh_token: String,
+    token: TokenResponse,
+}
+
+impl<P: OAuthProvider> OAuthClient<P> {
+    pub async fn refresh_if_expired(
+        &self,
+        token: &TokenResponse,
+        leeway: Duration,
+    ) -> Result<TokenResponse, OAuthError> {
+        let expired = token
+            .expires_at()
+            .is_some_and(|expires_at| self.config().clock.now() + leeway >= expires_at);
+        if !expired {
+            return Ok(token.clone());
+        }
+
+        let Some(refresh_token) = &token.refresh_token else {
+            return Err(OAuthError::UnsupportedOperation(
+                "token is expired and has no refresh token".to_string(),
+            ));
+        };
+        let mut refreshed = self.refresh_token(refresh_token).await?;
+        if refreshed.refresh_token.is_none() {
+            refreshed.refresh_token = Some(refresh_token.clone());
+        }
+        Ok(refreshed)
+    }
+
+    // Single-flight refresh across clones of this client. Callers that present
+    // the same refresh token while a refresh is running wait for it and get its
+    // result instead of sending their own request, which matters for providers
+    // that rotate refresh tokens and reject the old one. A later call that did
+    // not overlap the refresh sends its own request; failures are not shared,
+    // so the next waiter tries again.
+    pub async fn refresh_token_coordinated(
+        &self,
+        refresh_token: &str,
+    ) -> Result<TokenResponse, OAuthError> {
+        let flight = &self.refresh_flight;
+        let seen = flight.completed.load(Ordering::SeqCst);
+        let mut last = flight.last.lock().await;
+        if let Some(completed) = last.as_ref()
+            && completed.generation > seen
+            && completed.refresh_token == refresh_token
+        {
+            return Ok(completed.token.clone());
+        }
+
+        let mut refreshed = self.refresh_token(refresh_token).await?;
+        if refreshed.refresh_token.is_none() {
+            refreshed.refresh_token = Some(refresh_token.to_string());
+        }
+        *last = Some(CompletedRefresh {
+            generation: flight.completed.fetch_add(1, Ordering::SeqCst) + 1,
+            refresh_token: refresh_token.to_string(),
+            token: refreshed.clone(),
+        });
+        Ok(refreshed)
+    }
+}
+
+impl<P: OAuthProvider + 'static> OAuthClient<P> {
+    pub fn spawn_auto_refresh(
+        self,
+        initial: TokenResponse,
+        obtained_at: SystemTime,
+    ) -> (
+        watch::Receiver<TokenResponse>,
+        JoinHandle<Result<(), OAuthError>>,
+    ) {
+        self.spawn_auto_refresh_with_policy(initial, obtained_at, RefreshPolicy::default())
+    }
+
+    pub fn spawn_auto_refresh_with_policy(
+        self,
+        initial: TokenResponse,
+        obtained_at: SystemTime,
+        policy: RefreshPolicy,
+    ) -> (
+        watch::Receiver<TokenResponse>,
+        JoinHandle<Result<(), OAuthError>>,
+    ) {
+        let (tx, rx) = watch::channel(initial.clone());
+        let handle = tokio::spawn(async move {
+            let mut current = initial;
+            let mut obtained_at = obtained_at;
+
+            loop {
+                let (Some(expires_in), Some(refresh_token)) =
+                    (current.expires_in, current.refresh_token.clone())
+                else {
+                    return Ok(());
+                };
+
+                let refresh_at = (obtained_at + Duration::from_secs(expires_in))
+                    .checked_sub(policy.refresh_skew)
+                    .unwrap_or(obtained_at);
+                let clock = self.config().clock.clone();
+                let wait = refresh_at.duration_since(clock.now()).unwrap_or_default();
+                clock.sleep(wait).await;
+
+                let mut backoff = INITIAL_BACKOFF.min(policy.max_backoff);
+                let mut failures = 0;
+                let mut refreshed = loop {
+                    match self.refresh_token(&refresh_token).await {
+                        Ok(token) => break token,
+                        Err(err) => {
+                            if let Some(observer) = &self.config().refresh_error_observer {
+                                observer(&err);
+                            }
+                            if is_permanent(&err) {
+                                return Err(err);
+                            }
+                            failures += 1;
+                            if let ErrorAction::StopAfter(limit) = policy.on_error
+                                && failures >= limit
+                            {
+                                return Err(err);
+                            }
+                            clock.sleep(backoff).await;
+                            backoff = (backoff * 2).min(policy.max_backoff);
+                        }
+                    }
+                };
+                if refreshed.refresh_token.is_none() {
+                    refreshed.refresh_token = Some(refresh_token);
+                }
+                obtained_at = clock.now();
+                current = refreshed;
+
+                if tx.send(current.clone()).is_err() {
+                    return Ok(());
+                }
+            }
+        });
+
+        (rx, handle)
+    }
+}
+
+fn is_permanent(err: &OAuthError) -> bool {
+    matches!(
+        err,
+        OAuthError::OAuthErrorResponse { error, .. }
+            if PERMANENT_REFRESH_ERRORS.contains(&error.as_str())
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, SystemTime};
+
+    use super::{ErrorAction, RefreshPolicy};
+    use crate::test_support::{MockResponse, MockServer, TestProvider};
+    use crate::{MockClock, OAuthClient, OAuthClientConfig, OAuthError, TokenResponse};
+
+    fn refresh_client(server: &MockServer) -> OAuthClient<TestProvider> {
+        let provider = TestProvider::new(server.leak_url("/token"));
+        let config = OAuthClientConfig::new("client-id", "http://localhost:8765/callback")
+            .with_insecure_endpoints(true);
+        OAuthClient::new(provider, config).unwrap()
+    }
+
+    fn expiring_token() -> TokenResponse {
+        TokenResponse {
+            access_token: "stale".to_string(),
+            refresh_token: Some("refresh".to_string()),
+            token_type: None,
+            scope: None,
+            expires_in: Some(0),
+            extra: HashMap::new(),
+            obtained_at: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn coordinated_refresh_is_shared_across_clones() {
+        let server = MockServer::start(vec![MockResponse::json(
+            200,
+            r#"{"access_token":"fresh","expires_in":3600}"#,
+        )]);
+        let client = refresh_client(&server);
+
+        let tasks: Vec<_> = (0..4)
+            .map(|_| {
+                let client = client.clone();
+                tokio::spawn(async move { client.refresh_token_coordinated("refresh").await })
+            })
+            .collect();
+        for task in tasks {
+            let token = task.await.unwrap().unwrap();
+            assert_eq!(token.access_token, "fresh");
+            assert_eq!(token.refresh_token.as_deref(), Some("refresh"));
+        }
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn coordinated_refresh_is_not_reused_by_later_calls() {
+        let server = MockServer::start(vec![
+            MockResponse::json(200, r#"{"access_token":"fresh"}"#),
+            MockResponse::json(200, r#"{"access_token":"fresher"}"#),
+        ]);
+        let client = refresh_client(&server);
+
+        let first = client.refresh_token_coordinated("refresh").await.unwrap();
+        let second = client.refresh_token_coordinated("refresh").await.unwrap();
+        assert_eq!(first.access_token, "fresh");
+        assert_eq!(second.access_token, "fresher");
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn auto_refresh_publishes_refreshed_token() {
+        let server = MockServer::start(vec![MockResponse::json(
+            200,
+            r#"{"access_token":"fresh","expires_in":3600}"#,
+        )]);
+        let client = refresh_client(&server);
+
+        let (mut rx, handle) = client.spawn_auto_refresh(expiring_token(), SystemTime::now());
+        tokio::time::timeout(Duration::from_secs(5), rx.changed())
+            .await
+            .unwrap()
+            .unwrap();
+        handle.abort();
+
+        let token = rx.borrow().clone();
+        assert_eq!(token.access_token, "fresh");
+        assert_eq!(token.refresh_token.as_deref(), Some("refresh"));
+        assert_eq!(
+            server.requests()[0]
+                .params()
+                .get("refresh_token")
+                .map(String::as_str),
+            Some("refresh")
+        );
+    }
+
+    #[tokio::test]
+    async fn auto_refresh_backs_off_until_refresh_succeeds() {
+        let server = MockServer::start(vec![
+            MockResponse::json(500, r#"{"error":"server_error"}"#),
+            MockResponse::json(500, r#"{"error":"server_error"}"#),
+            MockResponse::json(200, r#"{"access_token":"fresh","expires_in":3600}"#),
+        ]);
+        let policy = RefreshPolicy {
+            max_backoff: Duration::from_millis(10),
+            on_error: ErrorAction::StopAfter(3),
+            ..RefreshPolicy::default()
+        };
+
+        let (mut rx, handle) = refresh_client(&server).spawn_auto_refresh_with_policy(
+            expiring_token(),
+            SystemTime::now(),
+            policy,
+        );
+        tokio::time::timeout(Duration::from_secs(5), rx.changed())
+            .await
+            .unwrap()
+            .unwrap();
+        handle.abort();
+
+        assert_eq!(rx.borrow().access_token, "fresh");
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn auto_refresh_stops_after_consecutive_failures() {
+        let server = MockServer::start(vec![
+            MockResponse::json(500, r#"{"error":"server_error"}"#),
+            MockResponse::json(500, r#"{"error":"server_error"}"#),
+        ]);
+        let policy = RefreshPolicy {
+            max_backoff: Duration::from_millis(10),
+            on_error: ErrorAction::StopAfter(2),
+            ..RefreshPolicy::default()
+        };
+
+        let (rx, handle) = refresh_client(&server).spawn_auto_refresh_with_policy(
+            expiring_token(),
+            SystemTime::now(),
+            policy,
+        );
+        let result = tokio::time::timeout(Duration::from_secs(5), handle)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert!(matches!(
+            result,
+            Err(OAuthError::OAuthErrorResponse { ref error, .. }) if error == "server_error"
+        ));
+        assert_eq!(rx.borrow().access_token, "stale");
+    }
+
+    #[tokio::test]
+    async fn auto_refresh_reports_each_failure_and_stops_on_invalid_grant() {
+        let server = MockServer::start(vec![
+            MockResponse::json(500, r#"{"error":"server_error"}"#),
+            MockResponse::json(400, r#"{"error":"invalid_grant"}"#),
+            MockResponse::json(200, r#"{"access_token":"fresh","expires_in":3600}"#),
+        ]);
+        let observed = Arc::new(Mutex::new(Vec::new()));
+        let recorded = observed.clone();
+        let provider = TestProvider::new(server.leak_url("/token"));
+        let config = OAuthClientConfig::new("client-id", "http://localhost:8765/callback")
+            .with_insecure_endpoints(true)
+            .with_refresh_error_observer(Arc::new(move |err: &OAuthError| {
+                recorded.lock().unwrap().push(err.to_string())
+            }));
+        let client = OAuthClient::new(provider, config).unwrap();
+        let policy = RefreshPolicy {
+            max_backoff: Duration::from_millis(10),
+            ..RefreshPolicy::default()
+        };
+
+        let (rx, handle) =
+            client.spawn_auto_refresh_with_policy(expiring_token(), SystemTime::now(), policy);
+        let result = tokio::time::timeout(Duration::from_secs(5), handle)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert!(matches!(
+            result,
+            Err(OAuthError::OAuthErrorResponse { ref error, .. }) if error == "invalid_grant"
+        ));
+        let observed = observed.lock().unwrap();
+        assert_eq!(observed.len(), 2);
+        assert!(observed[0].contains("server_error"));
+        assert!(observed[1].contains("invalid_grant"));
+        assert_eq!(server.requests().len(), 2);
+        assert_eq!(rx.borrow().access_token, "stale");
+    }
+
+    #[tokio::test]
+    async fn auto_refresh_ends_cleanly_without_an_expiry() {
+        let server = MockServer::start(Vec::new());
+        let initial = TokenResponse {
+            expires_in: None,
+            ..expiring_token()
+        };
+
+        let (_rx, handle) = refresh_client(&server).spawn_auto_refresh(initial, SystemTime::now());
+        let result = tokio::time::timeout(Duration::from_secs(5), handle)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert!(result.is_ok());
+        assert!(server.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn mock_clock_triggers_refresh_when_advanced_past_expiry() {
+        let server = MockServer::start(vec![MockResponse::json(
+            200,
+            r#"{"access_token":"fresh","expires_in":3600}"#,
+        )]);
+        let clock = Arc::new(MockClock::new(SystemTime::UNIX_EPOCH));
+        let provider = TestProvider::new(server.leak_url("/token"));
+        let config = OAuthClientConfig::new("client-id", "http://localhost:8765/callback")
+            .with_insecure_endpoints(true)
+            .with_clock(clock.clone());
+        let client = OAuthClient::new(provider, config).unwrap();
+        let initial = TokenResponse {
+            expires_in: Some(3600),
+            ..expiring_token()
+        };
+
+        let (mut rx, handle) = client.spawn_auto_refresh(initial, SystemTime::UNIX_EPOCH);
+        tokio::task::yield_now().await;
+        assert!(server.requests().is_empty());
+
+        clock.advance(Duration::from_secs(3600));
+        tokio::time::timeout(Duration::from_secs(5), rx.changed())
+            .await
+            .unwrap()
+            .unwrap();
+        handle.abort();
+
+        assert_eq!(rx.borrow().access_token, "fresh");
+    }
+
+    #[tokio::test]
+    async fn refresh_if_expired_carries_forward_refresh_token() {
+        let server = MockServer::start(vec![MockResponse::json(
+            200,
+            r#"{"access_token":"fresh","expires_in":3600}"#,
+        )]);
+        let clock = Arc::new(MockClock::new(SystemTime::UNIX_EPOCH));
+        let provider = TestProvider::new(server.leak_url("/token"));
+        let config = OAuthClientConfig::new("client-id", "http://localhost:8765/callback")
+            .with_insecure_endpoints(true)
+            .with_clock(clock.clone());
+        let client = OAuthClient::new(provider, config).unwrap();
+        let token = TokenResponse {
+            expires_in: Some(3600),
+            obtained_at: Some(SystemTime::UNIX_EPOCH),
+            ..expiring_token()
+        };
+
+        let same = client
+            .refresh_if_expired(&token, Duration::from_secs(30))
+            .await
+            .unwrap();
+        assert_eq!(same.access_token, "stale");
+        assert!(server.requests().is_empty());
+
+        clock.advance(Duration::from_secs(3580));
+        let refreshed = client
+            .refresh_if_expired(&token, Duration::from_secs(30))
+            .await
+            .unwrap();
+        assert_eq!(refreshed.access_token, "fresh");
+        assert_eq!(refreshed.refresh_token.as_deref(), Some("refresh"));
+        assert_eq!(server.requests().len(), 1);
+    }
+}
@@ -0,0 +1,119 @@
+use crate::client::{HttpReply, status_error};
+use crate::{OAuthClient, OAuthError, OAuthProvider, TokenRequestFormat};
+
+impl<P: OAuthProvider> OAuthClient<P> {
+    pub async fn revoke_token(
+        &self,
+        token: &str,
+        token_type_hint: Option<&str>,
+    ) -> Result<(), OAuthError> {
+        let url = self.provider().revocation_url().ok_or_else(|| {
+            OAuthError::UnsupportedOperation(format!(
+                "{} does not support token revocation",
+                self.provider().id()
+            ))
+        })?;
+
+        let mut payload = vec![("token".to_string(), token.to_string())];
+        if let Some(hint) = token_type_hint {
+            payload.push(("token_type_hint".to_string(), hint.to_string()));
+        }
+        payload.push(("client_id".to_string(), self.config().client_id.clone()));
+        if let Some(secret) = &self.config().client_secret {
+            payload.push(("client_secret".to_string(), secret.clone()));
+        }
+
+        // RFC 7009 §2.1 requires a form-encoded body whatever the provider's
+        // token request format; §2.2: any 200 is success and the body carries
+        // no meaning.
+        let HttpReply { status, body, .. } = self
+            .post_payload_as(url, &payload, TokenRequestFormat::Form)
+            .await?;
+        if !status.is_success() {
+            return Err(status_error(status, body));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use crate::test_support::{MockResponse, MockServer, TestProvider};
+    use crate::{OAuthClient, OAuthClientConfig, OAuthError};
+
+    fn revoking_client(server: &MockServer) -> OAuthClient<TestProvider> {
+        let provider = TestProvider {
+            revocation_url: Some(server.leak_url("/revoke")),
+            ..TestProvider::new(server.leak_url("/token"))
+        };
+        let config = OAuthClientConfig::new("client-id", "http://localhost:8765/callback")
+            .with_insecure_endpoints(true);
+        OAuthClient::new(provider, config).unwrap()
+    }
+
+    #[tokio::test]
+    async fn revoke_token_posts_token_and_hint() {
+        let server = MockServer::start(vec![MockResponse::new(200, "text/plain", "")]);
+        let client = revoking_client(&server);
+
+        client
+            .revoke_token("refresh", Some("refresh_token"))
+            .await
+            .unwrap();
+
+        let request = &server.requests()[0];
+        assert_eq!(request.path, "/revoke");
+        assert_eq!(
+            request.header("content-type"),
+            Some("application/x-www-form-urlencoded")
+        );
+        assert_eq!(
+            request.header("user-agent"),
+            Some(concat!("ai-oauth/", env!("CARGO_PKG_VERSION")))
+        );
+        let params = request.params();
+        assert_eq!(params.get("token").map(String::as_str), Some("refresh"));
+        assert_eq!(
+            params.get("token_type_hint").map(String::as_str),
+            Some("refresh_token")
+        );
+        assert_eq!(
+            params.get("client_id").map(String::as_str),
+            Some("client-id")
+        );
+    }
+
+    #[tokio::test]
+    async fn body_logger_masks_the_revoked_token() {
+        let server = MockServer::start(vec![MockResponse::new(200, "text/plain", "")]);
+        let logged = Arc::new(Mutex::new(Vec::new()));
+        let recorded = logged.clone();
+        let provider = TestProvider {
+            revocation_url: Some(server.leak_url("/revoke")),
+            ..TestProvider::new(server.leak_url("/token"))
+        };
+        let config = OAuthClientConfig::new("client-id", "http://localhost:8765/callback")
+            .with_insecure_endpoints(true)
+            .with_body_logger(Arc::new(move |body: &str| {
+                recorded.lock().unwrap().push(body.to_string())
+            }));
+        let client = OAuthClient::new(provider, config).unwrap();
+
+        client.revoke_token("secret-refresh", None).await.unwrap();
+
+        let logged = logged.lock().unwrap();
+        assert!(logged[0].contains("token=***"));
+        assert!(!logged[0].contains("secret-refresh"));
+    }
+
+    #[tokio::test]
+    async fn revoke_token_requires_a_revocation_url() {
+        let config = OAuthClientConfig::new("client-id", "http://localhost:8765/callback");
+        let client =
+            OAuthClient::new(TestProvider::new("https://example.com/token"), config).unwrap();
+        let error = client.revoke_token("access", None).await.unwrap_err();
+        assert!(matches!(error, OAuthError::UnsupportedOperation(_)));
+    }
+}
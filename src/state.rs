@@ -0,0 +1,252 @@
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::pkce::fill_random;
+use crate::{OAuthError, PkcePair, RngFallback};
+
+const NONCE_BYTES: usize = 16;
+const HMAC_BLOCK_BYTES: usize = 64;
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub enum StateStrategy {
+    #[default]
+    Verifier,
+    // The secret is never written out; a deserialized strategy has an empty
+    // secret and refuses to issue or check states until one is supplied again.
+    Timestamped {
+        #[serde(skip)]
+        secret: Vec<u8>,
+        #[serde(with = "crate::duration_ms")]
+        max_age: Duration,
+    },
+}
+
+impl StateStrategy {
+    pub fn timestamped(secret: impl Into<Vec<u8>>, max_age: Duration) -> Self {
+        Self::Timestamped {
+            secret: secret.into(),
+            max_age,
+        }
+    }
+
+    pub(crate) fn generate(
+        &self,
+        pkce: &PkcePair,
+        now: SystemTime,
+        fallback: Option<&RngFallback>,
+    ) -> Result<String, OAuthError> {
+        match self {
+            Self::Verifier => Ok(pkce.code_verifier.clone()),
+            Self::Timestamped { secret, .. } => {
+                require_secret(secret)?;
+                issue_timestamped(secret, now, fallback)
+            }
+        }
+    }
+
+    pub(crate) fn validate(&self, state: &str, now: SystemTime) -> Result<(), OAuthError> {
+        let Self::Timestamped { secret, max_age } = self else {
+            return Ok(());
+        };
+        require_secret(secret)?;
+
+        let mismatch = || OAuthError::StateMismatch {
+            expected: "signed timestamped state".to_string(),
+            received: state.to_string(),
+        };
+
+        let (message, mac) = state.rsplit_once('.').ok_or_else(mismatch)?;
+        let mac = URL_SAFE_NO_PAD.decode(mac).map_err(|_| mismatch())?;
+        if !constant_time_eq(&hmac_sha256(secret, message.as_bytes()), &mac) {
+            return Err(mismatch());
+        }
+
+        let issued_at = message
+            .split_once('.')
+            .and_then(|(issued_at, _)| issued_at.parse::<u64>().ok())
+            .ok_or_else(mismatch)?;
+        let issued_at = UNIX_EPOCH + Duration::from_secs(issued_at);
+        let age = now.duration_since(issued_at).unwrap_or_default();
+        if age > *max_age {
+            return Err(OAuthError::StateExpired {
+                age,
+                max_age: *max_age,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Debug for StateStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Verifier => f.write_str("Verifier"),
+            Self::Timestamped { max_age, .. } => f
+                .debug_struct("Timestamped")
+                .field("secret", &"[redacted]")
+                .field("max_age", max_age)
+                .finish(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct HmacStateCodec {
+    secret: Vec<u8>,
+}
+
+impl HmacStateCodec {
+    pub fn new(secret: impl Into<Vec<u8>>) -> Self {
+        Self {
+            secret: secret.into(),
+        }
+    }
+
+    pub fn encode(&self, payload: &str) -> String {
+        let message = URL_SAFE_NO_PAD.encode(payload);
+        let mac = hmac_sha256(&self.secret, message.as_bytes());
+        format!("{message}.{}", URL_SAFE_NO_PAD.encode(mac))
+    }
+
+    pub fn decode(&self, state: &str) -> Result<String, OAuthError> {
+        let mismatch = || OAuthError::StateMismatch {
+            expected: "hmac-signed state".to_string(),
+            received: state.to_string(),
+        };
+
+        let (message, mac) = state.split_once('.').ok_or_else(mismatch)?;
+        let mac = URL_SAFE_NO_PAD.decode(mac).map_err(|_| mismatch())?;
+        if !constant_time_eq(&hmac_sha256(&self.secret, message.as_bytes()), &mac) {
+            return Err(mismatch());
+        }
+        let payload = URL_SAFE_NO_PAD.decode(message).map_err(|_| mismatch())?;
+        String::from_utf8(payload).map_err(|_| mismatch())
+    }
+}
+
+impl fmt::Debug for HmacStateCodec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HmacStateCodec")
+            .field("secret", &"[redacted]")
+            .finish()
+    }
+}
+
+fn require_secret(secret: &[u8]) -> Result<(), OAuthError> {
+    if secret.is_empty() {
+        return Err(OAuthError::InvalidParam {
+            name: "state_strategy".to_string(),
+            message: "timestamped state secret is empty; it is not persisted with the config"
+                .to_string(),
+        });
+    }
+    Ok(())
+}
+
+pub(crate) fn issue_timestamped(
+    secret: &[u8],
+    now: SystemTime,
+    fallback: Option<&RngFallback>,
+) -> Result<String, OAuthError> {
+    let mut nonce = [0u8; NONCE_BYTES];
+    fill_random(&mut nonce, fallback)?;
+    let issued_at = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let message = format!("{issued_at}.{}", URL_SAFE_NO_PAD.encode(nonce));
+    let mac = hmac_sha256(secret, message.as_bytes());
+    Ok(format!("{message}.{}", URL_SAFE_NO_PAD.encode(mac)))
+}
+
+pub(crate) fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut block = [0u8; HMAC_BLOCK_BYTES];
+    if key.len() > HMAC_BLOCK_BYTES {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+
+    let mut inner = Sha256::new();
+    inner.update(block.map(|byte| byte ^ 0x36));
+    inner.update(message);
+
+    let mut outer = Sha256::new();
+    outer.update(block.map(|byte| byte ^ 0x5c));
+    outer.update(inner.finalize());
+    outer.finalize().into()
+}
+
+pub(crate) fn constant_time_eq(left: &[u8], right: &[u8]) -> bool {
+    left.len() == right.len()
+        && left
+            .iter()
+            .zip(right)
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use super::{HmacStateCodec, StateStrategy, hmac_sha256, issue_timestamped};
+    use crate::OAuthError;
+
+    const SECRET: &[u8] = b"state-secret";
+
+    #[test]
+    fn hmac_matches_rfc_4231_vector() {
+        let mac = hmac_sha256(b"Jefe", b"what do ya want for nothing?");
+        let hex: String = mac.iter().map(|byte| format!("{byte:02x}")).collect();
+        assert_eq!(
+            hex,
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn timestamped_state_rejects_tampered_issue_time() {
+        let strategy = StateStrategy::timestamped(SECRET, Duration::from_secs(600));
+        let now = SystemTime::now();
+        let state = issue_timestamped(SECRET, now, None).unwrap();
+        let (_, rest) = state.split_once('.').unwrap();
+        let forged = format!("9999999999.{rest}");
+        let result = strategy.validate(&forged, now);
+        assert!(matches!(result, Err(OAuthError::StateMismatch { .. })));
+    }
+
+    #[test]
+    fn hmac_codec_round_trips_and_rejects_tampering() {
+        let codec = HmacStateCodec::new(SECRET);
+        let state = codec.encode(r#"{"return_to":"/settings"}"#);
+        assert_eq!(
+            codec.decode(&state).unwrap(),
+            r#"{"return_to":"/settings"}"#
+        );
+
+        let (_, mac) = state.split_once('.').unwrap();
+        let forged = format!(
+            "{}.{mac}",
+            codec.encode("/admin").split_once('.').unwrap().0
+        );
+        let result = codec.decode(&forged);
+        assert!(matches!(result, Err(OAuthError::StateMismatch { .. })));
+
+        let other = HmacStateCodec::new(b"other-secret".to_vec());
+        assert!(other.decode(&state).is_err());
+    }
+
+    #[test]
+    fn timestamped_secret_is_not_serialized() {
+        let strategy = StateStrategy::timestamped(SECRET, Duration::from_secs(600));
+        let json = serde_json::to_string(&strategy).unwrap();
+        assert!(!json.contains("secret"));
+
+        let restored: StateStrategy = serde_json::from_str(&json).unwrap();
+        let result = restored.validate("state", SystemTime::now());
+        assert!(matches!(result, Err(OAuthError::InvalidParam { .. })));
+    }
+}
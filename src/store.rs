@@ -0,0 +1,241 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+#[cfg(unix)]
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{OAuthError, TokenResponse};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredToken {
+    pub token: TokenResponse,
+    pub obtained_at: SystemTime,
+}
+
+impl StoredToken {
+    pub fn new(token: TokenResponse, obtained_at: SystemTime) -> Self {
+        Self { token, obtained_at }
+    }
+}
+
+pub trait TokenStore {
+    fn load(&self) -> Result<Option<StoredToken>, OAuthError>;
+    fn save(&self, token: &StoredToken) -> Result<(), OAuthError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct FileTokenStore {
+    path: PathBuf,
+}
+
+impl FileTokenStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // Both take the store lock for the duration of the call.
+    pub fn load(&self) -> Result<Option<StoredToken>, OAuthError> {
+        self.lock()?.load()
+    }
+
+    pub fn save(&self, token: &StoredToken) -> Result<(), OAuthError> {
+        self.lock()?.save(token)
+    }
+
+    // Blocks until no other process or thread holds the store, so a caller can
+    // load, refresh, and save without racing another refresh. The lock is not
+    // re-entrant: while the guard is alive, use its `load` and `save`. Calling
+    // `FileTokenStore::load` or `save` from the same thread waits on the guard
+    // forever.
+    pub fn lock(&self) -> Result<FileTokenStoreGuard<'_>, OAuthError> {
+        let lock_file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(self.sibling_path("lock"))?;
+        lock_file.lock()?;
+        Ok(FileTokenStoreGuard {
+            store: self,
+            _lock_file: lock_file,
+        })
+    }
+
+    fn sibling_path(&self, extension: &str) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(".");
+        path.push(extension);
+        PathBuf::from(path)
+    }
+}
+
+impl TokenStore for FileTokenStore {
+    fn load(&self) -> Result<Option<StoredToken>, OAuthError> {
+        FileTokenStore::load(self)
+    }
+
+    fn save(&self, token: &StoredToken) -> Result<(), OAuthError> {
+        FileTokenStore::save(self, token)
+    }
+}
+
+#[derive(Debug)]
+pub struct FileTokenStoreGuard<'a> {
+    store: &'a FileTokenStore,
+    _lock_file: File,
+}
+
+impl FileTokenStoreGuard<'_> {
+    pub fn load(&self) -> Result<Option<StoredToken>, OAuthError> {
+        let contents = match fs::read_to_string(&self.store.path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        let token = serde_json::from_str(&contents).map_err(io::Error::from)?;
+        Ok(Some(token))
+    }
+
+    pub fn save(&self, token: &StoredToken) -> Result<(), OAuthError> {
+        let contents = serde_json::to_string_pretty(token).map_err(io::Error::from)?;
+        let temp_path = self.store.sibling_path("tmp");
+        let mut options = OpenOptions::new();
+        options.create(true).truncate(true).write(true);
+        // Token files hold bearer credentials, so keep them owner-only. `mode`
+        // only applies when the file is created, so a temp file left behind by
+        // an interrupted save is narrowed explicitly as well.
+        #[cfg(unix)]
+        options.mode(0o600);
+        let mut file = options.open(&temp_path)?;
+        #[cfg(unix)]
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+        file.write_all(contents.as_bytes())?;
+        fs::rename(&temp_path, &self.store.path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+    use std::time::{Duration, SystemTime};
+
+    use super::{FileTokenStore, StoredToken};
+    use crate::TokenResponse;
+
+    fn token(access_token: &str) -> TokenResponse {
+        TokenResponse {
+            access_token: access_token.to_string(),
+            refresh_token: Some(format!("{access_token}-refresh")),
+            token_type: None,
+            scope: None,
+            expires_in: Some(3600),
+            extra: HashMap::new(),
+            obtained_at: None,
+        }
+    }
+
+    #[test]
+    fn locked_refresh_happens_once_across_threads() {
+        let path = std::env::temp_dir().join(format!(
+            "ai-connect-store-{}-{}.json",
+            std::process::id(),
+            "locked_refresh"
+        ));
+        let store = FileTokenStore::new(&path);
+        store
+            .save(&StoredToken::new(token("stale"), SystemTime::now()))
+            .unwrap();
+
+        let refreshes = Arc::new(AtomicUsize::new(0));
+        let handles: Vec<_> = (0..4)
+            .map(|index| {
+                let store = store.clone();
+                let refreshes = refreshes.clone();
+                thread::spawn(move || {
+                    let guard = store.lock().unwrap();
+                    let current = guard.load().unwrap().unwrap();
+                    if current.token.access_token == "stale" {
+                        thread::sleep(Duration::from_millis(20));
+                        let fresh = token(&format!("fresh-{index}"));
+                        guard
+                            .save(&StoredToken::new(fresh, SystemTime::now()))
+                            .unwrap();
+                        refreshes.fetch_add(1, Ordering::SeqCst);
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let stored = store.load().unwrap().unwrap();
+        assert_eq!(refreshes.load(Ordering::SeqCst), 1);
+        assert!(stored.token.access_token.starts_with("fresh-"));
+        assert_eq!(
+            stored.token.refresh_token,
+            Some(format!("{}-refresh", stored.token.access_token))
+        );
+
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_file(store.sibling_path("lock"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn saved_token_file_is_owner_only() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = std::env::temp_dir().join(format!(
+            "ai-connect-store-{}-{}.json",
+            std::process::id(),
+            "owner_only"
+        ));
+        let store = FileTokenStore::new(&path);
+        store
+            .save(&StoredToken::new(token("access"), SystemTime::now()))
+            .unwrap();
+
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_file(store.sibling_path("lock"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn stale_temp_file_does_not_widen_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = std::env::temp_dir().join(format!(
+            "ai-connect-store-{}-{}.json",
+            std::process::id(),
+            "stale_temp"
+        ));
+        let store = FileTokenStore::new(&path);
+        let temp_path = store.sibling_path("tmp");
+        std::fs::write(&temp_path, "left over").unwrap();
+        std::fs::set_permissions(&temp_path, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        store
+            .save(&StoredToken::new(token("access"), SystemTime::now()))
+            .unwrap();
+
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_file(store.sibling_path("lock"));
+    }
+}
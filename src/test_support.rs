@@ -0,0 +1,255 @@
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::OAuthProvider;
+
+#[derive(Debug, Clone)]
+pub(crate) struct MockResponse {
+    status: u16,
+    content_type: &'static str,
+    body: String,
+}
+
+impl MockResponse {
+    pub(crate) fn new(status: u16, content_type: &'static str, body: impl Into<String>) -> Self {
+        Self {
+            status,
+            content_type,
+            body: body.into(),
+        }
+    }
+
+    pub(crate) fn json(status: u16, body: impl Into<String>) -> Self {
+        Self::new(status, "application/json", body)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct RecordedRequest {
+    pub(crate) method: String,
+    pub(crate) path: String,
+    pub(crate) headers: Vec<(String, String)>,
+    pub(crate) body: String,
+}
+
+impl RecordedRequest {
+    pub(crate) fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub(crate) fn params(&self) -> HashMap<String, String> {
+        let is_json = self
+            .header("content-type")
+            .is_some_and(|value| value.starts_with("application/json"));
+        if is_json {
+            serde_json::from_str(&self.body).unwrap()
+        } else {
+            url::form_urlencoded::parse(self.body.as_bytes())
+                .into_owned()
+                .collect()
+        }
+    }
+}
+
+pub(crate) struct MockServer {
+    base_url: String,
+    requests: Arc<Mutex<Vec<RecordedRequest>>>,
+}
+
+impl MockServer {
+    pub(crate) fn start(responses: Vec<MockResponse>) -> Self {
+        Self::start_with(|_| responses)
+    }
+
+    pub(crate) fn start_with(build: impl FnOnce(&str) -> Vec<MockResponse>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let responses = build(&base_url);
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let recorded = requests.clone();
+        thread::spawn(move || {
+            for response in responses {
+                let Ok((stream, _)) = listener.accept() else {
+                    return;
+                };
+                handle_connection(stream, &response, &recorded);
+            }
+        });
+
+        Self { base_url, requests }
+    }
+
+    pub(crate) fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    pub(crate) fn leak_url(&self, path: &str) -> &'static str {
+        Box::leak(self.url(path).into_boxed_str())
+    }
+
+    pub(crate) fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+fn handle_connection(
+    mut stream: TcpStream,
+    response: &MockResponse,
+    requests: &Mutex<Vec<RecordedRequest>>,
+) {
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).unwrap();
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let path = parts.next().unwrap_or_default().to_string();
+
+    let mut headers = Vec::new();
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+    }
+
+    let length = headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.parse::<usize>().ok())
+        .unwrap_or(0);
+    let mut body = vec![0u8; length];
+    reader.read_exact(&mut body).unwrap();
+
+    requests.lock().unwrap().push(RecordedRequest {
+        method,
+        path,
+        headers,
+        body: String::from_utf8_lossy(&body).into_owned(),
+    });
+
+    let reply = format!(
+        "HTTP/1.1 {} Mock\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        response.content_type,
+        response.body.len(),
+        response.body
+    );
+    let _ = stream.write_all(reply.as_bytes());
+    let _ = stream.flush();
+}
+
+#[derive(Clone)]
+pub(crate) struct TestProvider {
+    pub(crate) token_url: &'static str,
+    pub(crate) device_authorization_url: Option<&'static str>,
+    pub(crate) revocation_url: Option<&'static str>,
+    pub(crate) introspection_url: Option<&'static str>,
+    pub(crate) emit_challenge_method: bool,
+    pub(crate) include_scope_in_token_request: bool,
+    pub(crate) code_grant_type: &'static str,
+    pub(crate) refresh_grant_type: &'static str,
+    pub(crate) token_field_aliases: &'static [(&'static str, &'static str)],
+    pub(crate) default_timeout: Option<Duration>,
+    pub(crate) success_html: Option<&'static str>,
+}
+
+impl TestProvider {
+    pub(crate) fn new(token_url: &'static str) -> Self {
+        Self {
+            token_url,
+            device_authorization_url: None,
+            revocation_url: None,
+            introspection_url: None,
+            emit_challenge_method: true,
+            include_scope_in_token_request: false,
+            code_grant_type: "authorization_code",
+            refresh_grant_type: "refresh_token",
+            token_field_aliases: &[],
+            default_timeout: None,
+            success_html: None,
+        }
+    }
+}
+
+impl OAuthProvider for TestProvider {
+    fn id(&self) -> &'static str {
+        "test"
+    }
+
+    fn authorize_url(&self) -> &'static str {
+        "https://example.com/authorize"
+    }
+
+    fn token_url(&self) -> &'static str {
+        self.token_url
+    }
+
+    fn default_scope(&self) -> &'static str {
+        "read"
+    }
+
+    fn device_authorization_url(&self) -> Option<&'static str> {
+        self.device_authorization_url
+    }
+
+    fn revocation_url(&self) -> Option<&'static str> {
+        self.revocation_url
+    }
+
+    fn introspection_url(&self) -> Option<&'static str> {
+        self.introspection_url
+    }
+
+    fn default_timeout(&self) -> Option<Duration> {
+        self.default_timeout
+    }
+
+    fn success_html(&self) -> Option<&str> {
+        self.success_html
+    }
+
+    fn refresh_params(&self) -> Vec<(String, String)> {
+        vec![("refresh_only".to_string(), "true".to_string())]
+    }
+
+    fn token_headers_for(&self, payload: &HashMap<String, String>) -> Vec<(String, String)> {
+        payload
+            .get("grant_type")
+            .map(|grant_type| vec![("x-grant-type".to_string(), grant_type.clone())])
+            .unwrap_or_default()
+    }
+
+    fn token_field_aliases(&self) -> &[(&str, &str)] {
+        self.token_field_aliases
+    }
+
+    fn emit_challenge_method(&self) -> bool {
+        self.emit_challenge_method
+    }
+
+    fn include_scope_in_token_request(&self) -> bool {
+        self.include_scope_in_token_request
+    }
+
+    fn authorization_code_grant_type(&self) -> &str {
+        self.code_grant_type
+    }
+
+    fn refresh_grant_type(&self) -> &str {
+        self.refresh_grant_type
+    }
+}
@@ -1,9 +1,12 @@
 use std::collections::HashMap;
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use serde::{Deserialize, Serialize};
 use url::Url;
 
-use crate::OAuthError;
+use crate::client::{DEFAULT_MAX_RESPONSE_BYTES, HttpReply, parse_token_response};
+use crate::{IdTokenClaims, OAuthError};
 
 #[derive(Debug, Clone)]
 pub struct AuthorizationRequest {
@@ -11,28 +14,158 @@ pub struct AuthorizationRequest {
     pub pkce: crate::PkcePair,
     pub state: String,
     pub scope: String,
+    pub nonce: Option<String>,
+    pub warnings: Vec<AuthorizationWarning>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuthorizationWarning {
+    PromptNone,
+    PromptNoneWithLoginHint,
+    UnknownRedirectUri(String),
+}
+
+impl fmt::Display for AuthorizationWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::PromptNone => f.write_str(
+                "prompt=none shows no login UI; providers return login_required without an existing session",
+            ),
+            Self::PromptNoneWithLoginHint => f.write_str(
+                "prompt=none combined with login_hint is rejected by some providers",
+            ),
+            Self::UnknownRedirectUri(uri) => write!(
+                f,
+                "redirect uri {uri} is not registered with this provider and will likely be rejected"
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OAuthErrorResponse {
+    pub error: String,
+    pub error_description: Option<String>,
+    pub error_uri: Option<String>,
+}
+
+impl OAuthErrorResponse {
+    pub fn from_body(body: &str) -> Option<Self> {
+        serde_json::from_str(body).ok()
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BearerChallenge {
+    pub realm: Option<String>,
+    pub error: Option<String>,
+    pub error_description: Option<String>,
+    pub scope: Option<String>,
+}
+
+// Parses the auth-params of a `WWW-Authenticate: Bearer ...` header
+// (RFC 6750 §3). Non-Bearer challenges yield an empty `BearerChallenge`.
+pub fn parse_bearer_challenge(header: &str) -> BearerChallenge {
+    let mut challenge = BearerChallenge::default();
+    let header = header.trim();
+    let (scheme, params) = header
+        .split_once(char::is_whitespace)
+        .unwrap_or((header, ""));
+    if !scheme.eq_ignore_ascii_case("bearer") {
+        return challenge;
+    }
+
+    let mut chars = params.chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace() || *c == ',').is_some() {}
+        let name: String = std::iter::from_fn(|| chars.next_if(|c| *c != '=')).collect();
+        if chars.next().is_none() {
+            break;
+        }
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+
+        let mut value = String::new();
+        if chars.next_if_eq(&'"').is_some() {
+            while let Some(c) = chars.next() {
+                match c {
+                    '"' => break,
+                    '\\' => value.extend(chars.next()),
+                    _ => value.push(c),
+                }
+            }
+        } else {
+            value.extend(std::iter::from_fn(|| chars.next_if(|c| *c != ',')));
+            value.truncate(value.trim_end().len());
+        }
+
+        let slot = match name.trim().to_ascii_lowercase().as_str() {
+            "realm" => &mut challenge.realm,
+            "error" => &mut challenge.error,
+            "error_description" => &mut challenge.error_description,
+            "scope" => &mut challenge.scope,
+            _ => continue,
+        };
+        *slot = Some(value);
+    }
+    challenge
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Prompt {
+    None,
+    Login,
+    Consent,
+    SelectAccount,
+}
+
+impl Prompt {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Login => "login",
+            Self::Consent => "consent",
+            Self::SelectAccount => "select_account",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExpiryState {
+    Fresh,
+    ExpiringSoon,
+    Expired,
+    Unknown,
 }
 
 #[derive(Debug, Clone)]
 pub struct AuthorizationResponse {
     pub code: String,
     pub state: Option<String>,
+    // Set when the provider echoed a second state after `#` in the code.
+    pub fragment_state: Option<String>,
 }
 
 impl AuthorizationResponse {
     pub fn from_callback(code: &str, state: Option<&str>) -> Self {
-        if state.is_none() {
-            if let Some((code_part, state_part)) = code.split_once('#') {
-                return Self {
+        if let Some((code_part, state_part)) = code.split_once('#') {
+            return match state {
+                None => Self {
                     code: code_part.to_string(),
                     state: Some(state_part.to_string()),
-                };
-            }
+                    fragment_state: None,
+                },
+                Some(state) => Self {
+                    code: code_part.to_string(),
+                    state: Some(state.to_string()),
+                    fragment_state: Some(state_part.to_string()),
+                },
+            };
         }
 
         Self {
             code: code.to_string(),
             state: state.map(str::to_string),
+            fragment_state: None,
         }
     }
 
@@ -40,41 +173,189 @@ impl AuthorizationResponse {
         let url = Url::parse(callback_url)?;
         let mut code = None;
         let mut state = None;
+        let mut error = None;
+        let mut error_description = None;
 
         for (key, value) in url.query_pairs() {
             match key.as_ref() {
                 "code" => code = Some(value.to_string()),
                 "state" => state = Some(value.to_string()),
+                "error" => error = Some(value.to_string()),
+                "error_description" => error_description = Some(value.to_string()),
                 _ => {}
             }
         }
 
+        if let Some(error) = error {
+            return Err(OAuthError::AuthorizationDenied {
+                error,
+                error_description,
+            });
+        }
         let code = code.ok_or(OAuthError::MissingAuthorizationCode)?;
         Ok(Self::from_callback(&code, state.as_deref()))
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct TokenResponse {
     pub access_token: String,
     pub refresh_token: Option<String>,
     pub token_type: Option<String>,
     pub scope: Option<String>,
     pub expires_in: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub obtained_at: Option<SystemTime>,
     #[serde(flatten)]
     pub extra: HashMap<String, serde_json::Value>,
 }
 
+impl fmt::Debug for TokenResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // `extra` can carry an id_token, so only its keys are shown.
+        let mut extra: Vec<&String> = self.extra.keys().collect();
+        extra.sort();
+        f.debug_struct("TokenResponse")
+            .field("access_token", &"[redacted]")
+            .field(
+                "refresh_token",
+                &self.refresh_token.as_ref().map(|_| "[redacted]"),
+            )
+            .field("token_type", &self.token_type)
+            .field("scope", &self.scope)
+            .field("expires_in", &self.expires_in)
+            .field("obtained_at", &self.obtained_at)
+            .field("extra", &extra)
+            .finish()
+    }
+}
+
+impl TokenResponse {
+    pub async fn from_http_response(response: reqwest::Response) -> Result<Self, OAuthError> {
+        let reply = HttpReply::from_response(response, DEFAULT_MAX_RESPONSE_BYTES).await?;
+        let mut token = parse_token_response(reply, &[])?;
+        token.obtained_at = Some(SystemTime::now());
+        Ok(token)
+    }
+
+    pub fn expires_at(&self) -> Option<SystemTime> {
+        Some(self.obtained_at? + Duration::from_secs(self.expires_in?))
+    }
+
+    pub fn is_expired(&self, leeway: Duration) -> bool {
+        self.expires_at()
+            .is_some_and(|expires_at| SystemTime::now() + leeway >= expires_at)
+    }
+
+    pub fn to_env_pairs(&self, prefix: &str) -> Vec<(String, String)> {
+        let mut pairs = vec![(format!("{prefix}_ACCESS_TOKEN"), self.access_token.clone())];
+        if let Some(refresh_token) = &self.refresh_token {
+            pairs.push((format!("{prefix}_REFRESH_TOKEN"), refresh_token.clone()));
+        }
+        if let Some(expires_in) = self.expires_in {
+            pairs.push((format!("{prefix}_EXPIRES_IN"), expires_in.to_string()));
+        }
+        pairs
+    }
+
+    pub fn expiry_state(
+        &self,
+        obtained_at: SystemTime,
+        now: SystemTime,
+        soon: Duration,
+    ) -> ExpiryState {
+        let Some(expires_in) = self.expires_in else {
+            return ExpiryState::Unknown;
+        };
+        let expires_at = obtained_at + Duration::from_secs(expires_in);
+        if now >= expires_at {
+            ExpiryState::Expired
+        } else if now + soon >= expires_at {
+            ExpiryState::ExpiringSoon
+        } else {
+            ExpiryState::Fresh
+        }
+    }
+
+    pub fn granted_scopes(&self) -> Vec<&str> {
+        self.scope
+            .as_deref()
+            .map(|scope| scope.split_ascii_whitespace().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn id_token(&self) -> Option<&str> {
+        self.extra.get("id_token")?.as_str()
+    }
+
+    pub fn decode_id_token_claims(&self) -> Result<IdTokenClaims, OAuthError> {
+        let id_token = self
+            .id_token()
+            .ok_or_else(|| OAuthError::InvalidJwt("token response has no id_token".to_string()))?;
+        crate::jwt::decode_claims(id_token)
+    }
+
+    pub fn verify_nonce(&self, expected: &str) -> Result<IdTokenClaims, OAuthError> {
+        let claims = self.decode_id_token_claims()?;
+        if claims.nonce.as_deref() != Some(expected) {
+            return Err(OAuthError::NonceMismatch {
+                expected: expected.to_string(),
+                received: claims.nonce,
+            });
+        }
+        Ok(claims)
+    }
+
+    pub fn refresh_token_exp(&self) -> Option<SystemTime> {
+        let claims = crate::jwt::decode_payload(self.refresh_token.as_deref()?)?;
+        let exp = claims.get("exp")?.as_u64()?;
+        Some(UNIX_EPOCH + Duration::from_secs(exp))
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::AuthorizationResponse;
+    use std::collections::HashMap;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+
+    use super::{
+        AuthorizationResponse, BearerChallenge, ExpiryState, TokenResponse, parse_bearer_challenge,
+    };
     use crate::OAuthError;
 
+    #[test]
+    fn parses_bearer_challenge_with_scope() {
+        let challenge = parse_bearer_challenge(
+            r#"Bearer realm="api.example.com", error="insufficient_scope", error_description="The request requires \"admin\" access", scope="read write admin""#,
+        );
+        assert_eq!(
+            challenge,
+            BearerChallenge {
+                realm: Some("api.example.com".to_string()),
+                error: Some("insufficient_scope".to_string()),
+                error_description: Some(r#"The request requires "admin" access"#.to_string()),
+                scope: Some("read write admin".to_string()),
+            }
+        );
+        assert_eq!(
+            parse_bearer_challenge("Basic realm=\"x\""),
+            BearerChallenge::default()
+        );
+    }
+
     #[test]
     fn from_callback_splits_state_from_code() {
         let response = AuthorizationResponse::from_callback("abc123#state456", None);
         assert_eq!(response.code, "abc123");
         assert_eq!(response.state.as_deref(), Some("state456"));
+        assert_eq!(response.fragment_state, None);
+
+        let response = AuthorizationResponse::from_callback("abc123#fragment", Some("query"));
+        assert_eq!(response.code, "abc123");
+        assert_eq!(response.state.as_deref(), Some("query"));
+        assert_eq!(response.fragment_state.as_deref(), Some("fragment"));
     }
 
     #[test]
@@ -91,4 +372,195 @@ mod tests {
         let result = AuthorizationResponse::from_url("http://localhost/callback?state=state456");
         assert!(matches!(result, Err(OAuthError::MissingAuthorizationCode)));
     }
+
+    #[test]
+    fn from_url_surfaces_authorization_errors() {
+        let result = AuthorizationResponse::from_url(
+            "http://localhost/callback?error=access_denied&error_description=User+declined&state=s",
+        );
+        let Err(OAuthError::AuthorizationDenied {
+            error,
+            error_description,
+        }) = result
+        else {
+            panic!("expected AuthorizationDenied, got {result:?}");
+        };
+        assert_eq!(error, "access_denied");
+        assert_eq!(error_description.as_deref(), Some("User declined"));
+    }
+
+    #[test]
+    fn refresh_token_exp_decodes_jwt_refresh_tokens() {
+        let payload = URL_SAFE_NO_PAD.encode(r#"{"exp":2000000000,"sub":"user"}"#);
+        let mut token = TokenResponse {
+            access_token: "access".to_string(),
+            refresh_token: Some(format!("eyJhbGciOiJub25lIn0.{payload}.signature")),
+            token_type: None,
+            scope: None,
+            expires_in: None,
+            extra: HashMap::new(),
+            obtained_at: None,
+        };
+        assert_eq!(
+            token.refresh_token_exp(),
+            Some(UNIX_EPOCH + Duration::from_secs(2_000_000_000))
+        );
+
+        token.refresh_token = Some("opaque-refresh-token".to_string());
+        assert_eq!(token.refresh_token_exp(), None);
+    }
+
+    #[test]
+    fn expiry_uses_obtained_at_and_leeway() {
+        let mut token: TokenResponse =
+            serde_json::from_str(r#"{"access_token":"access","expires_in":60}"#).unwrap();
+        assert_eq!(token.expires_at(), None);
+        assert!(!token.is_expired(Duration::ZERO));
+
+        let obtained_at = SystemTime::now();
+        token.obtained_at = Some(obtained_at);
+        assert_eq!(
+            token.expires_at(),
+            Some(obtained_at + Duration::from_secs(60))
+        );
+        assert!(!token.is_expired(Duration::from_secs(30)));
+        assert!(token.is_expired(Duration::from_secs(90)));
+
+        let saved = serde_json::to_string(&token).unwrap();
+        let restored: TokenResponse = serde_json::from_str(&saved).unwrap();
+        assert_eq!(restored.obtained_at, Some(obtained_at));
+        assert!(restored.extra.is_empty());
+
+        token.obtained_at = None;
+        assert!(
+            !serde_json::to_string(&token)
+                .unwrap()
+                .contains("obtained_at")
+        );
+    }
+
+    #[test]
+    fn granted_scopes_tolerates_irregular_whitespace() {
+        let mut token: TokenResponse = serde_json::from_str(
+            r#"{"access_token":"access","scope":" openid\temail  profile\t "}"#,
+        )
+        .unwrap();
+        assert_eq!(token.granted_scopes(), vec!["openid", "email", "profile"]);
+
+        token.scope = None;
+        assert!(token.granted_scopes().is_empty());
+    }
+
+    #[test]
+    fn decodes_id_token_claims_from_extra_fields() {
+        let payload = URL_SAFE_NO_PAD.encode(
+            r#"{"sub":"user-1","email":"a@example.com","name":"A","aud":"client-id","iss":"https://accounts.google.com","exp":2000000000,"email_verified":true}"#,
+        );
+        let body = format!(
+            r#"{{"access_token":"access","id_token":"eyJhbGciOiJSUzI1NiJ9.{payload}.sig"}}"#
+        );
+        let token: TokenResponse = serde_json::from_str(&body).unwrap();
+
+        let claims = token.decode_id_token_claims().unwrap();
+        assert_eq!(claims.sub.as_deref(), Some("user-1"));
+        assert_eq!(claims.email.as_deref(), Some("a@example.com"));
+        assert_eq!(claims.aud, vec!["client-id"]);
+        assert_eq!(claims.exp, Some(2_000_000_000));
+        assert_eq!(claims.extra["email_verified"], true);
+
+        let token: TokenResponse =
+            serde_json::from_str(r#"{"access_token":"access","id_token":"not-a-jwt"}"#).unwrap();
+        assert!(matches!(
+            token.decode_id_token_claims(),
+            Err(OAuthError::InvalidJwt(_))
+        ));
+    }
+
+    #[test]
+    fn verify_nonce_compares_the_id_token_claim() {
+        let payload = URL_SAFE_NO_PAD.encode(r#"{"sub":"user-1","nonce":"n-123"}"#);
+        let body = format!(
+            r#"{{"access_token":"access","id_token":"eyJhbGciOiJSUzI1NiJ9.{payload}.sig"}}"#
+        );
+        let token: TokenResponse = serde_json::from_str(&body).unwrap();
+
+        assert_eq!(
+            token.verify_nonce("n-123").unwrap().nonce.as_deref(),
+            Some("n-123")
+        );
+        assert!(matches!(
+            token.verify_nonce("other"),
+            Err(OAuthError::NonceMismatch {
+                received: Some(_),
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn expiry_state_covers_each_state() {
+        let mut token: TokenResponse =
+            serde_json::from_str(r#"{"access_token":"access","expires_in":3600}"#).unwrap();
+        let soon = Duration::from_secs(300);
+        let at = |secs| UNIX_EPOCH + Duration::from_secs(secs);
+
+        assert_eq!(token.expiry_state(at(0), at(60), soon), ExpiryState::Fresh);
+        assert_eq!(
+            token.expiry_state(at(0), at(3400), soon),
+            ExpiryState::ExpiringSoon
+        );
+        assert_eq!(
+            token.expiry_state(at(0), at(3600), soon),
+            ExpiryState::Expired
+        );
+
+        token.expires_in = None;
+        assert_eq!(
+            token.expiry_state(at(0), at(60), soon),
+            ExpiryState::Unknown
+        );
+    }
+
+    #[test]
+    fn debug_output_redacts_tokens() {
+        let token: TokenResponse = serde_json::from_str(
+            r#"{"access_token":"secret-access","refresh_token":"secret-refresh","id_token":"secret-id","scope":"read"}"#,
+        )
+        .unwrap();
+        let debug = format!("{token:?}");
+        for secret in ["secret-access", "secret-refresh", "secret-id"] {
+            assert!(!debug.contains(secret), "{secret} leaked: {debug}");
+        }
+        assert!(debug.contains("[redacted]"));
+        assert!(debug.contains(r#"scope: Some("read")"#));
+        assert!(debug.contains("id_token"));
+    }
+
+    #[test]
+    fn to_env_pairs_omits_absent_fields() {
+        let mut token = TokenResponse {
+            access_token: "access".to_string(),
+            refresh_token: Some("refresh".to_string()),
+            token_type: None,
+            scope: None,
+            expires_in: Some(3600),
+            extra: HashMap::new(),
+            obtained_at: None,
+        };
+        assert_eq!(
+            token.to_env_pairs("OPENAI"),
+            vec![
+                ("OPENAI_ACCESS_TOKEN".to_string(), "access".to_string()),
+                ("OPENAI_REFRESH_TOKEN".to_string(), "refresh".to_string()),
+                ("OPENAI_EXPIRES_IN".to_string(), "3600".to_string()),
+            ]
+        );
+
+        token.refresh_token = None;
+        token.expires_in = None;
+        assert_eq!(
+            token.to_env_pairs("OPENAI"),
+            vec![("OPENAI_ACCESS_TOKEN".to_string(), "access".to_string())]
+        );
+    }
 }
@@ -0,0 +1,83 @@
+#![cfg(feature = "local-server")]
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::thread;
+
+use ai_connect::{GenericProvider, LocalServerConfig, OAuthClient, OAuthClientConfig};
+
+fn serve_token_once(body: &'static str) -> (String, thread::JoinHandle<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/token", listener.local_addr().unwrap());
+    let handle = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut length = 0;
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if line.trim_end().is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':')
+                && name.eq_ignore_ascii_case("content-length")
+            {
+                length = value.trim().parse().unwrap();
+            }
+        }
+        let mut request_body = vec![0u8; length];
+        reader.read_exact(&mut request_body).unwrap();
+
+        let reply = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        );
+        stream.write_all(reply.as_bytes()).unwrap();
+        String::from_utf8(request_body).unwrap()
+    });
+    (url, handle)
+}
+
+#[tokio::test]
+async fn local_flow_exchanges_callback_code_for_tokens() {
+    let (token_url, token_server) = serve_token_once(
+        r#"{"access_token":"access","refresh_token":"refresh","token_type":"Bearer","expires_in":3600}"#,
+    );
+    let provider = GenericProvider::new("generic", "http://127.0.0.1/authorize", token_url)
+        .with_default_scope("read");
+    // Port 0 lets the OS pick the port while the server binds it, so no other
+    // process can take it in between.
+    let config = OAuthClientConfig::new("client-id", "http://localhost/callback")
+        .with_insecure_endpoints(true)
+        .with_local_server_config(LocalServerConfig::new("127.0.0.1", 0, "/callback"));
+    let client = OAuthClient::new(provider, config).unwrap();
+
+    let mut port = 0;
+    let token = client
+        .run_local_flow_with_addr(|auth, addr| {
+            port = addr.port();
+            let callback = format!(
+                "http://127.0.0.1:{port}/callback?code=auth-code&state={}",
+                auth.state
+            );
+            tokio::spawn(async move {
+                let response = reqwest::get(callback).await.unwrap();
+                assert!(response.status().is_success());
+            });
+            Ok(())
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(token.access_token, "access");
+    assert_eq!(token.refresh_token.as_deref(), Some("refresh"));
+    assert_eq!(token.expires_in, Some(3600));
+
+    let request: serde_json::Value = serde_json::from_str(&token_server.join().unwrap()).unwrap();
+    assert_eq!(request["code"], "auth-code");
+    assert_eq!(request["grant_type"], "authorization_code");
+    assert_eq!(
+        request["redirect_uri"],
+        format!("http://127.0.0.1:{port}/callback")
+    );
+}